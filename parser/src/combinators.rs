@@ -1,4 +1,6 @@
-use crate::{BoxedParser, Parser, ParserInput};
+use std::ops::Range;
+
+use crate::{no_progress, BoxedParser, Parser, ParserError, ParserInput, ParserResult};
 
 pub fn one_of<'a, I, O, P>(vec: Vec<P>) -> impl Parser<I, Output = O> + 'a
 where
@@ -35,3 +37,567 @@ where
 {
     l.then(p).right().then(r).left()
 }
+
+pub fn optional<'a, I, O, P>(p: P) -> impl Parser<I, Output = Option<O>> + 'a
+where
+    I: ParserInput + Clone + 'a,
+    P: Parser<I, Output = O> + 'a,
+{
+    move |input: I| match p.parse(input.clone()) {
+        Ok((output, remaining)) => Ok((Some(output), remaining)),
+        Err(error) if error.fatal => Err(error),
+        Err(_) => Ok((None, input)),
+    }
+}
+
+/// Parses one or more `p` separated by `sep`. A separator must be followed by another `p`,
+/// so that the error of a bad element is reported rather than left to whatever comes next.
+pub fn sep_by1<'a, I, O, P, OS, PS>(p: P, sep: PS) -> impl Parser<I, Output = Vec<O>> + 'a
+where
+    I: ParserInput + Clone + 'a,
+    P: Parser<I, Output = O> + 'a,
+    PS: Parser<I, Output = OS> + 'a,
+{
+    move |input: I| {
+        let (first, remaining) = p.parse(input)?;
+        separated(&p, &sep, first, remaining)
+    }
+}
+
+/// Parses zero or more `p` separated by `sep`, see `sep_by1`.
+pub fn sep_by<'a, I, O, P, OS, PS>(p: P, sep: PS) -> impl Parser<I, Output = Vec<O>> + 'a
+where
+    I: ParserInput + Clone + 'a,
+    P: Parser<I, Output = O> + 'a,
+    PS: Parser<I, Output = OS> + 'a,
+{
+    move |input: I| match p.parse(input.clone()) {
+        Ok((first, remaining)) => separated(&p, &sep, first, remaining),
+        Err(error) if error.fatal => Err(error),
+        Err(_) => Ok((Vec::new(), input)),
+    }
+}
+
+/// Parses the separated elements after the first one.
+fn separated<I, O, P, OS, PS>(p: &P, sep: &PS, first: O, input: I) -> ParserResult<I, Vec<O>>
+where
+    I: ParserInput + Clone,
+    P: Parser<I, Output = O>,
+    PS: Parser<I, Output = OS>,
+{
+    let mut total_output = vec![first];
+    let mut remaining_input = input;
+    loop {
+        let element_input = match sep.parse(remaining_input.clone()) {
+            Ok((_, remaining)) => remaining,
+            Err(error) if error.fatal => return Err(error),
+            Err(_) => return Ok((total_output, remaining_input)),
+        };
+        match p.parse(element_input) {
+            Ok((_, remaining)) if remaining.position() == remaining_input.position() => {
+                return Err(no_progress(remaining_input));
+            }
+            Ok((output, remaining)) => {
+                total_output.push(output);
+                remaining_input = remaining;
+            }
+            Err(error) => return Err(error),
+        }
+    }
+}
+
+/// Parses `p` until `end` succeeds, consuming `end` as well.
+pub fn many_till<'a, I, O, P, OE, PE>(p: P, end: PE) -> impl Parser<I, Output = Vec<O>> + 'a
+where
+    I: ParserInput + Clone + 'a,
+    P: Parser<I, Output = O> + 'a,
+    PE: Parser<I, Output = OE> + 'a,
+{
+    move |input: I| {
+        let mut total_output = Vec::new();
+        let mut remaining_input = input;
+        loop {
+            let end_error = match end.parse(remaining_input.clone()) {
+                Ok((_, remaining)) => return Ok((total_output, remaining)),
+                Err(error) if error.fatal => return Err(error),
+                Err(error) => error,
+            };
+            match p.parse(remaining_input.clone()) {
+                Ok((_, remaining)) if remaining.position() == remaining_input.position() => {
+                    return Err(no_progress(remaining_input));
+                }
+                Ok((output, remaining)) => {
+                    total_output.push(output);
+                    remaining_input = remaining;
+                }
+                Err(error) => return Err(end_error.merge(error)),
+            }
+        }
+    }
+}
+
+/// Runs `p` without consuming any input.
+pub fn peek<'a, I, O, P>(p: P) -> impl Parser<I, Output = O> + 'a
+where
+    I: ParserInput + Clone + 'a,
+    P: Parser<I, Output = O> + 'a,
+{
+    move |input: I| p.parse(input.clone()).map(|(output, _)| (output, input))
+}
+
+/// Runs `p` and pairs its output with the range it consumed, leading trivia excluded.
+pub fn spanned<'a, I, O, P>(p: P) -> impl Parser<I, Output = (O, Range<usize>)> + 'a
+where
+    I: ParserInput + Clone + 'a,
+    P: Parser<I, Output = O> + 'a,
+{
+    move |input: I| {
+        let start = input.clone().skip_trivia().position();
+        let (output, remaining) = p.parse(input)?;
+        let span = start..remaining.position().max(start);
+        Ok(((output, span), remaining))
+    }
+}
+
+/// Succeeds without consuming any input when `p` fails.
+pub fn not_followed_by<'a, I, O, P>(p: P) -> impl Parser<I, Output = ()> + 'a
+where
+    I: ParserInput + Clone + 'a,
+    P: Parser<I, Output = O> + 'a,
+{
+    move |input: I| match p.parse(input.clone()) {
+        Ok((_, remaining)) => {
+            let span = input.position()..remaining.position();
+            Err(ParserError::new(input, span))
+        }
+        Err(_) => Ok(((), input)),
+    }
+}
+
+pub fn eof<'a, I>() -> impl Parser<I, Output = ()> + 'a
+where
+    I: ParserInput + 'a,
+{
+    |input: I| {
+        if input.is_empty() {
+            Ok(((), input))
+        } else {
+            Err(input.expected("end of input"))
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Associativity {
+    Left,
+    Right,
+}
+
+struct PrefixOperator<'a, I, O> {
+    parser: BoxedParser<'a, I, ()>,
+    precedence: u32,
+    build: Box<dyn Fn(O) -> O + 'a>,
+}
+
+struct InfixOperator<'a, I, O> {
+    parser: BoxedParser<'a, I, ()>,
+    precedence: u32,
+    associativity: Associativity,
+    build: Box<dyn Fn(O, O) -> O + 'a>,
+}
+
+struct PostfixOperator<'a, I, O> {
+    parser: BoxedParser<'a, I, ()>,
+    precedence: u32,
+    build: Box<dyn Fn(O) -> O + 'a>,
+}
+
+/// Prefix, infix and postfix operators for `operators`.
+/// Operators with a higher precedence bind tighter.
+pub struct OperatorTable<'a, I, O> {
+    prefix: Vec<PrefixOperator<'a, I, O>>,
+    infix: Vec<InfixOperator<'a, I, O>>,
+    postfix: Vec<PostfixOperator<'a, I, O>>,
+}
+
+impl<'a, I, O> Default for OperatorTable<'a, I, O> {
+    fn default() -> Self {
+        OperatorTable {
+            prefix: Vec::new(),
+            infix: Vec::new(),
+            postfix: Vec::new(),
+        }
+    }
+}
+
+impl<'a, I, O> OperatorTable<'a, I, O>
+where
+    I: ParserInput + 'a,
+{
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn prefix<OP, P, F>(mut self, parser: P, precedence: u32, build: F) -> Self
+    where
+        OP: 'a,
+        P: Parser<I, Output = OP> + 'a,
+        F: Fn(O) -> O + 'a,
+    {
+        self.prefix.push(PrefixOperator {
+            parser: parser.map(|_| ()).boxed(),
+            precedence,
+            build: Box::new(build),
+        });
+        self
+    }
+
+    pub fn infix<OP, P, F>(
+        mut self,
+        parser: P,
+        precedence: u32,
+        associativity: Associativity,
+        build: F,
+    ) -> Self
+    where
+        OP: 'a,
+        P: Parser<I, Output = OP> + 'a,
+        F: Fn(O, O) -> O + 'a,
+    {
+        self.infix.push(InfixOperator {
+            parser: parser.map(|_| ()).boxed(),
+            precedence,
+            associativity,
+            build: Box::new(build),
+        });
+        self
+    }
+
+    pub fn postfix<OP, P, F>(mut self, parser: P, precedence: u32, build: F) -> Self
+    where
+        OP: 'a,
+        P: Parser<I, Output = OP> + 'a,
+        F: Fn(O) -> O + 'a,
+    {
+        self.postfix.push(PostfixOperator {
+            parser: parser.map(|_| ()).boxed(),
+            precedence,
+            build: Box::new(build),
+        });
+        self
+    }
+}
+
+pub struct OperatorParser<'a, I, O, P> {
+    operand: P,
+    table: OperatorTable<'a, I, O>,
+}
+
+impl<'a, I, O, P> OperatorParser<'a, I, O, P>
+where
+    I: ParserInput + Clone,
+    P: Parser<I, Output = O>,
+{
+    fn parse_operand(&self, input: I) -> ParserResult<I, O> {
+        let mut error: Option<ParserError<I>> = None;
+        for operator in &self.table.prefix {
+            match operator.parser.parse(input.clone()) {
+                Ok(((), remaining)) => {
+                    let (operand, remaining) =
+                        self.parse_expression(remaining, operator.precedence)?;
+                    return Ok(((operator.build)(operand), remaining));
+                }
+                Err(e) if e.fatal => return Err(e),
+                Err(e) => error = Some(merge(error, e)),
+            }
+        }
+        self.operand.parse(input).map_err(|e| merge(error, e))
+    }
+
+    /// Parses operators binding at least as tight as `minimum` using precedence climbing.
+    fn parse_expression(&self, input: I, minimum: u32) -> ParserResult<I, O> {
+        let (mut left, mut remaining) = self.parse_operand(input)?;
+        'operators: loop {
+            for operator in &self.table.postfix {
+                if operator.precedence < minimum {
+                    continue;
+                }
+                match operator.parser.parse(remaining.clone()) {
+                    Ok(((), next)) => {
+                        left = (operator.build)(left);
+                        remaining = next;
+                        continue 'operators;
+                    }
+                    Err(error) if error.fatal => return Err(error),
+                    Err(_) => {}
+                }
+            }
+            for operator in &self.table.infix {
+                if operator.precedence < minimum {
+                    continue;
+                }
+                match operator.parser.parse(remaining.clone()) {
+                    Ok(((), next)) => {
+                        let next_minimum = match operator.associativity {
+                            Associativity::Left => operator.precedence + 1,
+                            Associativity::Right => operator.precedence,
+                        };
+                        let (right, next) = self.parse_expression(next, next_minimum)?;
+                        left = (operator.build)(left, right);
+                        remaining = next;
+                        continue 'operators;
+                    }
+                    Err(error) if error.fatal => return Err(error),
+                    Err(_) => {}
+                }
+            }
+            return Ok((left, remaining));
+        }
+    }
+}
+
+impl<'a, I, O, P> Parser<I> for OperatorParser<'a, I, O, P>
+where
+    I: ParserInput + Clone,
+    P: Parser<I, Output = O>,
+{
+    type Output = O;
+
+    fn parse(&self, input: I) -> ParserResult<I, Self::Output> {
+        self.parse_expression(input, 0)
+    }
+}
+
+fn merge<I>(error: Option<ParserError<I>>, other: ParserError<I>) -> ParserError<I> {
+    match error {
+        Some(error) => error.merge(other),
+        None => other,
+    }
+}
+
+/// Builds an operator precedence parser over `operand` from the operators in `table`.
+pub fn operators<'a, I, O, P>(
+    operand: P,
+    table: OperatorTable<'a, I, O>,
+) -> OperatorParser<'a, I, O, P>
+where
+    I: ParserInput + Clone,
+    P: Parser<I, Output = O>,
+{
+    OperatorParser { operand, table }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ops::Range;
+
+    use crate::ThenParserExtensions;
+
+    use super::*;
+
+    #[derive(Clone, Debug)]
+    struct TestInput<'a> {
+        text: &'a str,
+        position: usize,
+    }
+
+    impl<'a> ParserInput for TestInput<'a> {
+        fn position(&self) -> usize {
+            self.position
+        }
+
+        fn is_empty(&self) -> bool {
+            self.position == self.text.len()
+        }
+
+        fn span(&self) -> Range<usize> {
+            self.position..self.position + 1
+        }
+    }
+
+    fn character<'a>(expected: char) -> impl Parser<TestInput<'a>, Output = char> + 'a {
+        move |input: TestInput<'a>| match input.text[input.position..].chars().next() {
+            Some(c) if c == expected => Ok((
+                c,
+                TestInput {
+                    position: input.position + c.len_utf8(),
+                    ..input
+                },
+            )),
+            _ => Err(input.expected(format!("'{expected}'"))),
+        }
+    }
+
+    fn digit<'a>() -> impl Parser<TestInput<'a>, Output = String> + 'a {
+        |input: TestInput<'a>| match input.text[input.position..].chars().next() {
+            Some(c) if c.is_ascii_digit() => Ok((
+                c.to_string(),
+                TestInput {
+                    position: input.position + 1,
+                    ..input
+                },
+            )),
+            _ => Err(input.expected("a digit")),
+        }
+    }
+
+    fn arithmetic<'a>() -> impl Parser<TestInput<'a>, Output = String> + 'a {
+        let table = OperatorTable::new()
+            .prefix(character('-'), 5, |x| format!("(-{x})"))
+            .infix(character('+'), 1, Associativity::Left, |x, y| {
+                format!("({x}+{y})")
+            })
+            .infix(character('*'), 2, Associativity::Left, |x, y| {
+                format!("({x}*{y})")
+            })
+            .infix(character('^'), 3, Associativity::Right, |x, y| {
+                format!("({x}^{y})")
+            })
+            .postfix(character('!'), 9, |x| format!("({x}!)"));
+        operators(digit(), table)
+    }
+
+    fn parse(text: &str) -> ParserResult<TestInput<'_>, String> {
+        arithmetic().parse(input(text))
+    }
+
+    fn input(text: &str) -> TestInput<'_> {
+        TestInput { text, position: 0 }
+    }
+
+    #[test]
+    fn test_optional() {
+        let (output, remaining) = optional(character('a')).parse(input("ab")).unwrap();
+        assert_eq!(output, Some('a'));
+        assert_eq!(remaining.position, 1);
+        let (output, remaining) = optional(character('b')).parse(input("ab")).unwrap();
+        assert_eq!(output, None);
+        assert_eq!(remaining.position, 0);
+    }
+
+    #[test]
+    fn test_sep_by() {
+        let parser = sep_by(digit(), character(','));
+        let (output, remaining) = parser.parse(input("1,2,3;")).unwrap();
+        assert_eq!(output, vec!["1", "2", "3"]);
+        assert_eq!(remaining.position, 5);
+        let (output, remaining) = parser.parse(input("x")).unwrap();
+        assert!(output.is_empty());
+        assert_eq!(remaining.position, 0);
+        assert!(sep_by1(digit(), character(',')).parse(input("x")).is_err());
+
+        let error = parser.parse(input("1,2,x")).unwrap_err();
+        assert_eq!(error.to_string(), "expected a digit");
+        assert_eq!(error.span, 4..5);
+    }
+
+    #[test]
+    fn test_many_till() {
+        let parser = many_till(digit(), character(';'));
+        let (output, remaining) = parser.parse(input("12;3")).unwrap();
+        assert_eq!(output, vec!["1", "2"]);
+        assert_eq!(remaining.position, 3);
+        let error = parser.parse(input("12x")).unwrap_err();
+        assert_eq!(error.to_string(), "expected ';' or a digit");
+        assert_eq!(error.span, 2..3);
+    }
+
+    #[test]
+    fn test_counts() {
+        let (output, remaining) = digit().exactly(2).parse(input("123")).unwrap();
+        assert_eq!(output, vec!["1", "2"]);
+        assert_eq!(remaining.position, 2);
+        assert!(digit().exactly(2).parse(input("1x")).is_err());
+        let (output, _) = digit().at_most(2).parse(input("123")).unwrap();
+        assert_eq!(output.len(), 2);
+        let (output, _) = digit().between_counts(1, 5).parse(input("123")).unwrap();
+        assert_eq!(output.len(), 3);
+        assert!(digit().between_counts(1, 5).parse(input("x")).is_err());
+    }
+
+    #[test]
+    #[should_panic(expected = "between_counts(3, 2) can never succeed")]
+    fn test_counts_inverted() {
+        let _ = digit().between_counts(3, 2);
+    }
+
+    #[test]
+    fn test_no_progress() {
+        let error = optional(character('a'))
+            .at_least(0)
+            .parse(input("ab"))
+            .unwrap_err();
+        assert!(error.fatal);
+        assert_eq!(
+            error.to_string(),
+            "repeated parser succeeded without consuming input"
+        );
+        assert_eq!(error.span, 1..2);
+
+        let parser = sep_by(optional(character('a')), optional(character(',')));
+        assert!(parser.parse(input("b")).unwrap_err().fatal);
+        let parser = many_till(optional(character('a')), character(';'));
+        assert!(parser.parse(input("b")).unwrap_err().fatal);
+    }
+
+    #[test]
+    fn test_peek() {
+        let (output, remaining) = peek(character('a')).parse(input("ab")).unwrap();
+        assert_eq!(output, 'a');
+        assert_eq!(remaining.position, 0);
+        assert!(peek(character('b')).parse(input("ab")).is_err());
+    }
+
+    #[test]
+    fn test_spanned() {
+        let parser = character('a').skip_then(spanned(character('b').at_least(1)));
+        let ((output, span), remaining) = parser.parse(input("abbc")).unwrap();
+        assert_eq!(output, vec!['b', 'b']);
+        assert_eq!(span, 1..3);
+        assert_eq!(remaining.position, 3);
+    }
+
+    #[test]
+    fn test_not_followed_by() {
+        let parser = character('a').then_skip(not_followed_by(character('b')));
+        assert!(parser.parse(input("ac")).is_ok());
+        let error = parser.parse(input("ab")).unwrap_err();
+        assert_eq!(error.to_string(), "unexpected input");
+        assert_eq!(error.span, 1..2);
+    }
+
+    #[test]
+    fn test_eof() {
+        let parser = character('a').then_skip(eof());
+        assert!(parser.parse(input("a")).is_ok());
+        let error = parser.parse(input("ab")).unwrap_err();
+        assert_eq!(error.to_string(), "expected end of input");
+        assert_eq!(error.span, 1..2);
+    }
+
+    #[test]
+    fn test_operators_precedence() {
+        let (output, remaining) = parse("1+2*3+4").unwrap();
+        assert_eq!(output, "((1+(2*3))+4)");
+        assert_eq!(remaining.position, 7);
+    }
+
+    #[test]
+    fn test_operators_associativity() {
+        assert_eq!(parse("1^2^3").unwrap().0, "(1^(2^3))");
+        assert_eq!(parse("1*2*3").unwrap().0, "((1*2)*3)");
+    }
+
+    #[test]
+    fn test_operators_prefix_postfix() {
+        assert_eq!(parse("-1!*2").unwrap().0, "((-(1!))*2)");
+        assert_eq!(parse("--1+2").unwrap().0, "((-(-1))+2)");
+    }
+
+    #[test]
+    fn test_operators_error() {
+        let error = parse("1+*").unwrap_err();
+        assert_eq!(error.to_string(), "expected '-' or a digit");
+        assert_eq!(error.span, 2..3);
+    }
+}
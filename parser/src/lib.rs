@@ -1,16 +1,146 @@
+use std::cmp::Ordering;
+use std::collections::BTreeSet;
+use std::fmt::{Debug, Display, Formatter};
 use std::ops::{Deref, Range};
 
+use crate::memo::{LeftRecursiveParser, MemoInput, MemoParser, ParserId};
+use crate::trace::TracedParser;
+
 pub mod combinators;
+pub mod memo;
+pub mod trace;
 
-pub type ParserError<I> = (String, I, Range<usize>);
 pub type ParserResult<I, O> = Result<(O, I), ParserError<I>>;
 pub type BoxedParser<'a, I, O> = Box<dyn Parser<I, Output = O> + 'a>;
 
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ParserError<I> {
+    pub input: I,
+    pub span: Range<usize>,
+    pub expected: BTreeSet<String>,
+    pub message: Option<String>,
+    /// Whether the error occurred past a commit point and must not be backtracked.
+    pub fatal: bool,
+}
+
+impl<I> ParserError<I> {
+    pub fn new(input: I, span: Range<usize>) -> Self {
+        let span = if span.is_empty() {
+            span.start..span.start + 1
+        } else {
+            span
+        };
+        ParserError {
+            input,
+            span,
+            expected: BTreeSet::new(),
+            message: None,
+            fatal: false,
+        }
+    }
+
+    pub fn with_expected(mut self, item: impl Into<String>) -> Self {
+        self.expected.insert(item.into());
+        self
+    }
+
+    pub fn with_message(mut self, message: impl Into<String>) -> Self {
+        self.message = Some(message.into());
+        self
+    }
+
+    pub fn map_input<J, F>(self, f: F) -> ParserError<J>
+    where
+        F: FnOnce(I) -> J,
+    {
+        ParserError {
+            input: f(self.input),
+            span: self.span,
+            expected: self.expected,
+            message: self.message,
+            fatal: self.fatal,
+        }
+    }
+
+    pub fn with_fatal(mut self) -> Self {
+        self.fatal = true;
+        self
+    }
+
+    /// Combines the errors of two failed alternatives, keeping a fatal one or the one that got furthest.
+    /// Errors at the same position have their expected items merged.
+    pub fn merge(self, other: Self) -> Self {
+        if self.fatal != other.fatal {
+            return if self.fatal { self } else { other };
+        }
+        match self.span.start.cmp(&other.span.start) {
+            Ordering::Less => other,
+            Ordering::Greater => self,
+            Ordering::Equal => {
+                let mut merged = self;
+                merged.expected.extend(other.expected);
+                merged.message = merged.message.or(other.message);
+                merged
+            }
+        }
+    }
+}
+
+impl<I> Display for ParserError<I> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if let Some(message) = &self.message {
+            return write!(f, "{message}");
+        }
+        let expected = self.expected.iter().collect::<Vec<_>>();
+        match expected.split_last() {
+            None => write!(f, "unexpected input"),
+            Some((last, [])) => write!(f, "expected {last}"),
+            Some((last, rest)) => {
+                let rest = rest
+                    .iter()
+                    .map(|item| item.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                write!(f, "expected {rest} or {last}")
+            }
+        }
+    }
+}
+
+impl<I> std::error::Error for ParserError<I> where I: Debug {}
+
 pub trait ParserInput
 where
     Self: Sized,
 {
-    fn error(self, message: String) -> ParserError<Self>;
+    /// The offset of this input from the start of the source.
+    fn position(&self) -> usize;
+
+    /// Whether the input has been fully consumed.
+    fn is_empty(&self) -> bool;
+
+    /// The range of the next item of input, used to locate errors.
+    fn span(&self) -> Range<usize>;
+
+    fn expected(self, item: impl Into<String>) -> ParserError<Self> {
+        let span = self.span();
+        ParserError::new(self, span).with_expected(item)
+    }
+
+    fn error(self, message: impl Into<String>) -> ParserError<Self> {
+        let span = self.span();
+        ParserError::new(self, span).with_message(message)
+    }
+
+    /// Skips input that carries no meaning, such as whitespace, before the next item.
+    fn skip_trivia(self) -> Self {
+        self
+    }
+
+    /// Whether `recover` should turn errors into fallback outputs instead of failing.
+    fn is_recovering(&self) -> bool {
+        false
+    }
 }
 
 pub trait Parser<I>
@@ -57,7 +187,93 @@ where
         Self: Sized,
         I: Clone,
     {
-        AtLeastParser::new(minimum, self)
+        AtLeastParser::new(minimum, None, self)
+    }
+
+    fn at_most(self, maximum: u64) -> AtLeastParser<Self>
+    where
+        Self: Sized,
+        I: Clone,
+    {
+        AtLeastParser::new(0, Some(maximum), self)
+    }
+
+    fn exactly(self, count: u64) -> AtLeastParser<Self>
+    where
+        Self: Sized,
+        I: Clone,
+    {
+        AtLeastParser::new(count, Some(count), self)
+    }
+
+    /// Repeats this parser from `minimum` to `maximum` times, which must not be fewer.
+    fn between_counts(self, minimum: u64, maximum: u64) -> AtLeastParser<Self>
+    where
+        Self: Sized,
+        I: Clone,
+    {
+        debug_assert!(
+            minimum <= maximum,
+            "between_counts({minimum}, {maximum}) can never succeed"
+        );
+        AtLeastParser::new(minimum, Some(maximum), self)
+    }
+
+    fn cut(self) -> CutParser<Self>
+    where
+        Self: Sized,
+    {
+        CutParser::new(self)
+    }
+
+    fn recover<S, OS, F>(self, sync: S, fallback: F) -> RecoverParser<Self, S, F>
+    where
+        Self: Sized,
+        I: Clone,
+        S: Parser<I, Output = OS>,
+        F: Fn(ParserError<I>) -> Self::Output,
+    {
+        RecoverParser::new(self, sync, fallback)
+    }
+
+    /// Reports failures that consumed nothing as expecting `label` instead of the inner expected items.
+    fn label(self, label: &'static str) -> LabelParser<Self>
+    where
+        Self: Sized,
+        I: Clone,
+    {
+        LabelParser::new(label, self)
+    }
+
+    /// Records calls to this parser when tracing is enabled, see `trace::enable`.
+    fn traced(self, name: &'static str) -> TracedParser<Self>
+    where
+        Self: Sized,
+    {
+        TracedParser::new(name, self)
+    }
+
+    /// Caches the results of this parser by input position. The cache is shared by the parsers
+    /// built at the same place of the same type, see `ParserId`, whatever their `name`.
+    #[track_caller]
+    fn memoize(self, name: &'static str) -> MemoParser<Self>
+    where
+        Self: Sized,
+        I: MemoInput,
+        Self::Output: Clone + 'static,
+    {
+        MemoParser::new(ParserId::new::<Self, Self::Output>(name), self)
+    }
+
+    /// Allows this parser to call itself at the start of its input, see `LeftRecursiveParser`.
+    #[track_caller]
+    fn left_recursive(self, name: &'static str) -> LeftRecursiveParser<Self>
+    where
+        Self: Sized,
+        I: MemoInput,
+        Self::Output: Clone + 'static,
+    {
+        LeftRecursiveParser::new(ParserId::new::<Self, Self::Output>(name), self)
     }
 }
 
@@ -232,24 +448,41 @@ where
 
     fn parse(&self, input: I) -> ParserResult<I, Self::Output> {
         let input_clone = input.clone();
-        self.first_parser
-            .parse(input)
-            .or_else(|_| self.second_parser.parse(input_clone))
+        self.first_parser.parse(input).or_else(|first_error| {
+            if first_error.fatal {
+                return Err(first_error);
+            }
+            self.second_parser
+                .parse(input_clone)
+                .map_err(|second_error| first_error.merge(second_error))
+        })
     }
 }
 
+/// The error for a repeated parser that succeeds without consuming input and would loop forever.
+pub(crate) fn no_progress<I>(input: I) -> ParserError<I>
+where
+    I: ParserInput,
+{
+    input
+        .error("repeated parser succeeded without consuming input")
+        .with_fatal()
+}
+
+/// Repeats a parser at least `min` and, when given, at most `max` times.
 pub struct AtLeastParser<P> {
     min: u64,
+    max: Option<u64>,
     parser: P,
 }
 
 impl<P> AtLeastParser<P> {
-    fn new<I, O>(min: u64, parser: P) -> AtLeastParser<P>
+    fn new<I, O>(min: u64, max: Option<u64>, parser: P) -> AtLeastParser<P>
     where
         I: ParserInput,
         P: Parser<I, Output = O>,
     {
-        AtLeastParser { min, parser }
+        AtLeastParser { min, max, parser }
     }
 }
 
@@ -263,14 +496,129 @@ where
     fn parse(&self, input: I) -> ParserResult<I, Self::Output> {
         let mut total_output = Vec::new();
         let mut remaining_input = input;
-        while let Ok((output, remaining)) = self.parser.parse(remaining_input.clone()) {
-            total_output.push(output);
-            remaining_input = remaining
+        loop {
+            if self.max.is_some_and(|max| total_output.len() as u64 >= max) {
+                return Ok((total_output, remaining_input));
+            }
+            match self.parser.parse(remaining_input.clone()) {
+                Ok((_, remaining)) if remaining.position() == remaining_input.position() => {
+                    return Err(no_progress(remaining_input));
+                }
+                Ok((output, remaining)) => {
+                    total_output.push(output);
+                    remaining_input = remaining
+                }
+                Err(error) if error.fatal || (total_output.len() as u64) < self.min => {
+                    return Err(error)
+                }
+                Err(_) => return Ok((total_output, remaining_input)),
+            }
         }
-        if (total_output.len() as u64) < self.min {
-            Err(remaining_input.error("Unexpected input at this position.".to_string()))
-        } else {
-            Ok((total_output, remaining_input))
+    }
+}
+
+/// Commits to the current branch: errors past this point are fatal and not backtracked.
+pub struct CutParser<P> {
+    parser: P,
+}
+
+impl<P> CutParser<P> {
+    fn new<I, O>(parser: P) -> CutParser<P>
+    where
+        I: ParserInput,
+        P: Parser<I, Output = O>,
+    {
+        CutParser { parser }
+    }
+}
+
+impl<I, O, P> Parser<I> for CutParser<P>
+where
+    I: ParserInput,
+    P: Parser<I, Output = O>,
+{
+    type Output = O;
+
+    fn parse(&self, input: I) -> ParserResult<I, Self::Output> {
+        self.parser.parse(input).map_err(ParserError::with_fatal)
+    }
+}
+
+/// Recovers from errors when the input asks for it: `sync` skips the offending input
+/// starting at the failure position and `fallback` turns the error into an output.
+pub struct RecoverParser<P, S, F> {
+    parser: P,
+    sync: S,
+    fallback: F,
+}
+
+impl<P, S, F> RecoverParser<P, S, F> {
+    fn new<I, O, OS>(parser: P, sync: S, fallback: F) -> RecoverParser<P, S, F>
+    where
+        I: ParserInput,
+        P: Parser<I, Output = O>,
+        S: Parser<I, Output = OS>,
+        F: Fn(ParserError<I>) -> O,
+    {
+        RecoverParser {
+            parser,
+            sync,
+            fallback,
+        }
+    }
+}
+
+impl<I, O, OS, P, S, F> Parser<I> for RecoverParser<P, S, F>
+where
+    I: ParserInput + Clone,
+    P: Parser<I, Output = O>,
+    S: Parser<I, Output = OS>,
+    F: Fn(ParserError<I>) -> O,
+{
+    type Output = O;
+
+    fn parse(&self, input: I) -> ParserResult<I, Self::Output> {
+        let error = match self.parser.parse(input) {
+            Ok(result) => return Ok(result),
+            Err(error) if !error.input.is_recovering() => return Err(error),
+            Err(error) => error,
+        };
+        match self.sync.parse(error.input.clone()) {
+            Ok((_, remaining)) => Ok(((self.fallback)(error), remaining)),
+            Err(_) => Err(error),
         }
     }
 }
+
+pub struct LabelParser<P> {
+    label: &'static str,
+    parser: P,
+}
+
+impl<P> LabelParser<P> {
+    fn new<I, O>(label: &'static str, parser: P) -> LabelParser<P>
+    where
+        I: ParserInput,
+        P: Parser<I, Output = O>,
+    {
+        LabelParser { label, parser }
+    }
+}
+
+impl<I, O, P> Parser<I> for LabelParser<P>
+where
+    I: ParserInput + Clone,
+    P: Parser<I, Output = O>,
+{
+    type Output = O;
+
+    fn parse(&self, input: I) -> ParserResult<I, Self::Output> {
+        let start = input.clone().skip_trivia().position();
+        self.parser.parse(input).map_err(|mut error| {
+            if !error.fatal && error.input.position() == start {
+                error.expected = BTreeSet::from([self.label.to_string()]);
+            }
+            error
+        })
+    }
+}
@@ -0,0 +1,493 @@
+use std::any::{Any, TypeId};
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::fmt::{Debug, Formatter};
+use std::hash::{Hash, Hasher};
+use std::ops::Range;
+use std::panic::Location;
+
+use crate::{Parser, ParserError, ParserInput, ParserResult};
+
+/// The identity of a memoized parser: where it is built and what it is. Parsers rebuilt by
+/// each call of the same rule share their results, while distinct parsers never do,
+/// whatever their names.
+#[derive(Clone, Copy, Debug)]
+pub struct ParserId {
+    /// Only reported by `MemoTable::parsed`, it is not part of the identity.
+    name: &'static str,
+    location: &'static Location<'static>,
+    parser: &'static str,
+    output: TypeId,
+}
+
+impl ParserId {
+    #[track_caller]
+    pub(crate) fn new<P, O: 'static>(name: &'static str) -> Self {
+        ParserId {
+            name,
+            location: Location::caller(),
+            parser: std::any::type_name::<P>(),
+            output: TypeId::of::<O>(),
+        }
+    }
+}
+
+impl PartialEq for ParserId {
+    fn eq(&self, other: &Self) -> bool {
+        (self.location, self.parser, self.output) == (other.location, other.parser, other.output)
+    }
+}
+
+impl Eq for ParserId {}
+
+impl Hash for ParserId {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        (self.location, self.parser, self.output).hash(state)
+    }
+}
+
+type MemoKey = (ParserId, usize);
+type MemoResult = Result<(Box<dyn Any>, usize), ParserError<usize>>;
+
+/// Outputs that hold input positions, which must move along when memoized results
+/// are reused after an edit to the input.
+pub trait Relocatable {
+    fn relocate(&mut self, offset: isize);
+}
+
+struct MemoEntry {
+    result: MemoResult,
+    /// The end of the input looked at to produce the result.
+    examined: usize,
+    relocate: fn(&mut dyn Any, isize),
+}
+
+fn downcast<O>(result: &MemoResult) -> Option<Result<(O, usize), ParserError<usize>>>
+where
+    O: Clone + 'static,
+{
+    match result {
+        Ok((output, end)) => output
+            .downcast_ref::<O>()
+            .map(|output| Ok((output.clone(), *end))),
+        Err(error) => Some(Err(error.clone())),
+    }
+}
+
+fn store<I, O>(result: &ParserResult<I, O>) -> MemoResult
+where
+    I: ParserInput + Clone,
+    O: Clone + 'static,
+{
+    match result {
+        Ok((output, remaining)) => Ok((
+            Box::new(output.clone()) as Box<dyn Any>,
+            remaining.position(),
+        )),
+        Err(error) => Err(error.clone().map_input(|remaining| remaining.position())),
+    }
+}
+
+fn restore<I, O>(result: Result<(O, usize), ParserError<usize>>, input: &I) -> ParserResult<I, O>
+where
+    I: MemoInput,
+{
+    match result {
+        Ok((output, position)) => Ok((output, input.clone().advance_to(position))),
+        Err(error) => Err(error.map_input(|position| input.clone().advance_to(position))),
+    }
+}
+
+fn relocate<O>(output: &mut dyn Any, offset: isize)
+where
+    O: Relocatable + 'static,
+{
+    if let Some(output) = output.downcast_mut::<O>() {
+        output.relocate(offset);
+    }
+}
+
+/// Results of memoized parsers, keyed by parser identity and input position.
+#[derive(Default)]
+pub struct MemoTable {
+    entries: RefCell<HashMap<MemoKey, MemoEntry>>,
+    examined: Cell<usize>,
+    /// The current results of left-recursive rules being grown, see `LeftRecursiveParser`.
+    seeds: RefCell<HashMap<MemoKey, MemoResult>>,
+    /// Whether the parser currently running used a seed, so its result must not be memoized.
+    seeded: Cell<bool>,
+}
+
+impl MemoTable {
+    fn get<O>(&self, key: &MemoKey) -> Option<Result<(O, usize), ParserError<usize>>>
+    where
+        O: Clone + 'static,
+    {
+        let entries = self.entries.borrow();
+        let entry = entries.get(key)?;
+        self.examine(entry.examined);
+        downcast(&entry.result)
+    }
+
+    fn get_seed<O>(&self, key: &MemoKey) -> Option<Result<(O, usize), ParserError<usize>>>
+    where
+        O: Clone + 'static,
+    {
+        let seeds = self.seeds.borrow();
+        let seed = downcast(seeds.get(key)?);
+        self.seeded.set(true);
+        seed
+    }
+
+    fn insert(&self, key: MemoKey, entry: MemoEntry) {
+        self.entries.borrow_mut().insert(key, entry);
+    }
+
+    /// Records that the input up to `end` was looked at by the parser currently running.
+    pub fn examine(&self, end: usize) {
+        self.examined.set(self.examined.get().max(end));
+    }
+
+    /// Updates the table for an edit replacing `range` of the input with `length` bytes.
+    /// Results that looked at the edited range are dropped and results after it are moved.
+    pub fn edit(&self, range: Range<usize>, length: usize) {
+        let offset = length as isize - range.len() as isize;
+        let shift = |position: usize| position.saturating_add_signed(offset);
+        let mut entries = self.entries.borrow_mut();
+        *entries = std::mem::take(&mut *entries)
+            .into_iter()
+            .filter_map(|((id, start), mut entry)| {
+                if entry.examined <= range.start {
+                    return Some(((id, start), entry));
+                }
+                let after = start > range.end || (start == range.end && !range.is_empty());
+                if !after {
+                    return None;
+                }
+                entry.examined = shift(entry.examined);
+                entry.result = match entry.result {
+                    Ok((mut output, end)) => {
+                        (entry.relocate)(output.as_mut(), offset);
+                        Ok((output, shift(end)))
+                    }
+                    Err(mut error) => {
+                        error.span = shift(error.span.start)..shift(error.span.end);
+                        Err(error.map_input(shift))
+                    }
+                };
+                Some(((id, shift(start)), entry))
+            })
+            .collect();
+    }
+
+    /// The ranges of input successfully parsed by each memoized parser.
+    pub fn parsed(&self) -> Vec<(&'static str, Range<usize>)> {
+        self.entries
+            .borrow()
+            .iter()
+            .filter_map(|((id, start), entry)| match entry.result {
+                Ok((_, end)) => Some((id.name, *start..end)),
+                Err(_) => None,
+            })
+            .collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.borrow().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.borrow().is_empty()
+    }
+
+    pub fn clear(&self) {
+        self.entries.borrow_mut().clear()
+    }
+}
+
+impl Debug for MemoTable {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MemoTable")
+            .field("entries", &self.len())
+            .finish()
+    }
+}
+
+pub trait MemoInput
+where
+    Self: ParserInput + Clone,
+{
+    fn memo_table(&self) -> &MemoTable;
+
+    /// Moves the input forward to a position at or after the current one.
+    fn advance_to(self, position: usize) -> Self;
+}
+
+pub struct MemoParser<P> {
+    id: ParserId,
+    parser: P,
+}
+
+impl<P> MemoParser<P> {
+    pub(crate) fn new<I, O>(id: ParserId, parser: P) -> MemoParser<P>
+    where
+        I: MemoInput,
+        P: Parser<I, Output = O>,
+    {
+        MemoParser { id, parser }
+    }
+}
+
+impl<I, O, P> Parser<I> for MemoParser<P>
+where
+    I: MemoInput,
+    O: Clone + Relocatable + 'static,
+    P: Parser<I, Output = O>,
+{
+    type Output = O;
+
+    fn parse(&self, input: I) -> ParserResult<I, Self::Output> {
+        let start = input.position();
+        let key = (self.id, start);
+        if let Some(entry) = input.memo_table().get::<O>(&key) {
+            return restore(entry, &input);
+        }
+
+        let table = input.memo_table();
+        let examined = table.examined.replace(start);
+        let seeded = table.seeded.replace(false);
+        let result = self.parser.parse(input.clone());
+        let table = input.memo_table();
+        match &result {
+            Ok((_, remaining)) => table.examine(remaining.position() + 1),
+            Err(error) => table.examine(error.input.position() + 1),
+        }
+        if !table.seeded.get() {
+            let entry = MemoEntry {
+                result: store(&result),
+                examined: table.examined.get(),
+                relocate: relocate::<O>,
+            };
+            table.insert(key, entry);
+        }
+        table.examine(examined);
+        table.seeded.set(seeded || table.seeded.get());
+        result
+    }
+}
+
+/// A rule that may call itself at the start of its input, parsed by growing a seed:
+/// the recursive call first fails, then returns the previous result for as long as
+/// each attempt consumes more input than the last.
+pub struct LeftRecursiveParser<P> {
+    id: ParserId,
+    parser: P,
+}
+
+impl<P> LeftRecursiveParser<P> {
+    pub(crate) fn new<I, O>(id: ParserId, parser: P) -> LeftRecursiveParser<P>
+    where
+        I: MemoInput,
+        P: Parser<I, Output = O>,
+    {
+        LeftRecursiveParser { id, parser }
+    }
+}
+
+impl<I, O, P> Parser<I> for LeftRecursiveParser<P>
+where
+    I: MemoInput,
+    O: Clone + 'static,
+    P: Parser<I, Output = O>,
+{
+    type Output = O;
+
+    fn parse(&self, input: I) -> ParserResult<I, Self::Output> {
+        let start = input.position();
+        let key = (self.id, start);
+        if let Some(seed) = input.memo_table().get_seed::<O>(&key) {
+            return restore(seed, &input);
+        }
+
+        let span = input.span();
+        let failure = ParserError::new(start, span);
+        input
+            .memo_table()
+            .seeds
+            .borrow_mut()
+            .insert(key, Err(failure));
+        let seeded = input.memo_table().seeded.get();
+        let mut result = self.parser.parse(input.clone());
+        if let Ok((_, remaining)) = &result {
+            let mut end = remaining.position();
+            loop {
+                input
+                    .memo_table()
+                    .seeds
+                    .borrow_mut()
+                    .insert(key, store(&result));
+                match self.parser.parse(input.clone()) {
+                    Ok((output, remaining)) if remaining.position() > end => {
+                        end = remaining.position();
+                        result = Ok((output, remaining));
+                    }
+                    Err(error) if error.fatal => {
+                        result = Err(error);
+                        break;
+                    }
+                    _ => break,
+                }
+            }
+        }
+        let table = input.memo_table();
+        table.seeds.borrow_mut().remove(&key);
+        table.seeded.set(seeded);
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use super::*;
+    use crate::ThenParserExtensions;
+
+    #[derive(Clone, Debug)]
+    struct TestInput<'a> {
+        text: &'a str,
+        position: usize,
+        memo: Rc<MemoTable>,
+    }
+
+    impl<'a> TestInput<'a> {
+        fn new(text: &'a str) -> Self {
+            TestInput {
+                text,
+                position: 0,
+                memo: Rc::default(),
+            }
+        }
+    }
+
+    impl<'a> ParserInput for TestInput<'a> {
+        fn position(&self) -> usize {
+            self.position
+        }
+
+        fn is_empty(&self) -> bool {
+            self.position == self.text.len()
+        }
+
+        fn span(&self) -> Range<usize> {
+            self.position..self.position + 1
+        }
+    }
+
+    impl<'a> MemoInput for TestInput<'a> {
+        fn memo_table(&self) -> &MemoTable {
+            &self.memo
+        }
+
+        fn advance_to(self, position: usize) -> Self {
+            TestInput { position, ..self }
+        }
+    }
+
+    impl Relocatable for String {
+        fn relocate(&mut self, _offset: isize) {}
+    }
+
+    fn character<'a>(predicate: fn(char) -> bool) -> impl Parser<TestInput<'a>, Output = String> {
+        move |input: TestInput<'a>| match input.text[input.position..].chars().next() {
+            Some(c) if predicate(c) => {
+                let position = input.position + c.len_utf8();
+                Ok((c.to_string(), input.advance_to(position)))
+            }
+            _ => Err(ParserError::new(input.clone(), input.span())),
+        }
+    }
+
+    fn digit<'a>() -> impl Parser<TestInput<'a>, Output = String> {
+        character(|c| c.is_ascii_digit())
+    }
+
+    fn difference(input: TestInput) -> ParserResult<TestInput, String> {
+        difference
+            .then_skip(character(|c| c == '-'))
+            .then(digit().cut())
+            .map(|(x, y)| format!("({x}-{y})"))
+            .or_else(digit())
+            .left_recursive("difference")
+            .parse(input)
+    }
+
+    fn postfix(input: TestInput) -> ParserResult<TestInput, String> {
+        postfix
+            .memoize("postfix")
+            .then(character(|c| c == '!'))
+            .map(|(x, _)| format!("{x}!"))
+            .or_else(atom)
+            .left_recursive("postfix")
+            .parse(input)
+    }
+
+    fn atom(input: TestInput) -> ParserResult<TestInput, String> {
+        character(|c| c == '(')
+            .skip_then(postfix)
+            .then_skip(character(|c| c == ')'))
+            .map(|x| format!("({x})"))
+            .or_else(digit())
+            .memoize("atom")
+            .parse(input)
+    }
+
+    #[test]
+    fn test_left_recursive() {
+        let (output, remaining) = difference(TestInput::new("1-2-3+")).unwrap();
+        assert_eq!(output, "((1-2)-3)");
+        assert_eq!(remaining.position, 5);
+
+        let error = difference(TestInput::new("-1")).unwrap_err();
+        assert_eq!(error.span, 0..1);
+
+        let error = difference(TestInput::new("1-2-x")).unwrap_err();
+        assert!(error.fatal);
+        assert_eq!(error.span, 4..5);
+    }
+
+    #[test]
+    fn test_left_recursive_memoized() {
+        let input = TestInput::new("(1!)!!");
+        let (output, remaining) = postfix(input.clone()).unwrap();
+        assert_eq!(output, "(1!)!!");
+        assert!(remaining.is_empty());
+        assert_eq!(
+            postfix(input.clone()).unwrap().0,
+            postfix(TestInput::new("(1!)!!")).unwrap().0
+        );
+        assert!(input.memo.parsed().contains(&("atom", 0..4)));
+        assert!(!input.memo.parsed().iter().any(|(key, _)| *key == "postfix"));
+    }
+
+    #[test]
+    fn test_memoize_identity() {
+        let input = TestInput::new("(1)");
+        atom(input.clone()).unwrap();
+        let entries = input.memo.len();
+        atom(input.clone()).unwrap();
+        assert_eq!(input.memo.len(), entries);
+
+        let input = TestInput::new("x");
+        let letter = character(|c| c.is_ascii_alphabetic()).memoize("same");
+        let digit = digit().memoize("same");
+        assert!(letter.parse(input.clone()).is_ok());
+        assert!(digit.parse(input.clone()).is_err());
+
+        let named = |name| self::digit().memoize(name);
+        let input = TestInput::new("1");
+        assert!(named("first").parse(input.clone()).is_ok());
+        assert!(named("second").parse(input.clone()).is_ok());
+        assert_eq!(input.memo.parsed(), vec![("first", 0..1)]);
+    }
+}
@@ -0,0 +1,154 @@
+use std::cell::RefCell;
+use std::fmt::{Display, Formatter};
+
+use crate::{Parser, ParserInput, ParserResult};
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum TraceOutcome {
+    Success { end: usize },
+    Failure { position: usize, error: String },
+}
+
+/// A call to a traced parser along with the traced parsers it called.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TraceNode {
+    pub name: &'static str,
+    pub start: usize,
+    pub outcome: Option<TraceOutcome>,
+    pub children: Vec<TraceNode>,
+}
+
+impl TraceNode {
+    fn fmt_indented(&self, f: &mut Formatter<'_>, depth: usize) -> std::fmt::Result {
+        let indent = "  ".repeat(depth);
+        match &self.outcome {
+            Some(TraceOutcome::Success { end }) => {
+                writeln!(f, "{indent}{} {}..{} ok", self.name, self.start, end)?
+            }
+            Some(TraceOutcome::Failure { position, error }) => writeln!(
+                f,
+                "{indent}{} {} failed at {}: {}",
+                self.name, self.start, position, error
+            )?,
+            None => writeln!(f, "{indent}{} {} unfinished", self.name, self.start)?,
+        }
+        for child in &self.children {
+            child.fmt_indented(f, depth + 1)?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for TraceNode {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        self.fmt_indented(f, 0)
+    }
+}
+
+#[derive(Default)]
+struct Tracer {
+    stack: Vec<TraceNode>,
+    roots: Vec<TraceNode>,
+}
+
+thread_local! {
+    static TRACER: RefCell<Option<Tracer>> = const { RefCell::new(None) };
+}
+
+/// Starts recording traced parsers on the current thread.
+pub fn enable() {
+    TRACER.with_borrow_mut(|tracer| {
+        tracer.get_or_insert_with(Tracer::default);
+    });
+}
+
+/// Stops recording and discards anything recorded so far.
+pub fn disable() {
+    TRACER.with_borrow_mut(|tracer| *tracer = None);
+}
+
+pub fn is_enabled() -> bool {
+    TRACER.with_borrow(|tracer| tracer.is_some())
+}
+
+/// Takes the calls recorded so far, leaving recording enabled.
+pub fn take() -> Vec<TraceNode> {
+    TRACER.with_borrow_mut(|tracer| {
+        tracer
+            .as_mut()
+            .map(|tracer| std::mem::take(&mut tracer.roots))
+            .unwrap_or_default()
+    })
+}
+
+fn enter(name: &'static str, start: usize) -> bool {
+    TRACER.with_borrow_mut(|tracer| match tracer {
+        Some(tracer) => {
+            tracer.stack.push(TraceNode {
+                name,
+                start,
+                outcome: None,
+                children: Vec::new(),
+            });
+            true
+        }
+        None => false,
+    })
+}
+
+fn exit(outcome: TraceOutcome) {
+    TRACER.with_borrow_mut(|tracer| {
+        let Some(tracer) = tracer else {
+            return;
+        };
+        let Some(mut node) = tracer.stack.pop() else {
+            return;
+        };
+        node.outcome = Some(outcome);
+        match tracer.stack.last_mut() {
+            Some(parent) => parent.children.push(node),
+            None => tracer.roots.push(node),
+        }
+    })
+}
+
+pub struct TracedParser<P> {
+    name: &'static str,
+    parser: P,
+}
+
+impl<P> TracedParser<P> {
+    pub(crate) fn new<I, O>(name: &'static str, parser: P) -> TracedParser<P>
+    where
+        I: ParserInput,
+        P: Parser<I, Output = O>,
+    {
+        TracedParser { name, parser }
+    }
+}
+
+impl<I, O, P> Parser<I> for TracedParser<P>
+where
+    I: ParserInput,
+    P: Parser<I, Output = O>,
+{
+    type Output = O;
+
+    fn parse(&self, input: I) -> ParserResult<I, Self::Output> {
+        if !enter(self.name, input.position()) {
+            return self.parser.parse(input);
+        }
+        let result = self.parser.parse(input);
+        let outcome = match &result {
+            Ok((_, remaining)) => TraceOutcome::Success {
+                end: remaining.position(),
+            },
+            Err(error) => TraceOutcome::Failure {
+                position: error.input.position(),
+                error: error.to_string(),
+            },
+        };
+        exit(outcome);
+        result
+    }
+}
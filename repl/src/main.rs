@@ -1,47 +1,139 @@
-use std::io::{BufRead, Write};
+use std::io::{IsTerminal, Read, Write};
 
+use f_prime::diagnostics::{Diagnostic, Renderer};
 use f_prime::eval::full::FullBetaEvaluator;
 use f_prime::eval::TracingBetaReduction;
-use f_prime::expression::buffer::{Parsable, PositionedBuffer};
-use f_prime::expression::Expression;
-use f_prime::term::untyped::UntypedLambdaTerm;
+use f_prime::expression::numeral::NumeralEncoding;
+use f_prime::expression::program::{Definition, Program};
+use f_prime::expression::source::{Source, SourceId, SourceMap};
+use f_prime::expression::stream::{StreamBuffer, StreamError};
+use f_prime::expression::SyntaxError;
+use f_prime::term::untyped::{UntypedLambdaTerm, UntypedProgramTerm};
+use f_prime::term::Term;
 use f_prime::traverse::de_bruijn::convert::DeBruijnConverter;
 use f_prime::traverse::pretty_print::ExpressionPrettyPrinter;
+use f_prime_parser::trace;
 
 fn print_prompt() {
     print!(">> ");
     let _ = std::io::stdout().flush();
 }
 
-fn print_error() {
-    println!("!!");
-    print_prompt();
+fn print_continuation_prompt() {
+    print!(".. ");
+    let _ = std::io::stdout().flush();
 }
 
-fn main() -> Result<(), std::io::Error> {
-    print_prompt();
-    for line in std::io::stdin().lock().lines() {
-        if line.is_err() {
-            print_error();
-            continue;
+fn print_syntax_errors(sources: &SourceMap, id: SourceId, errors: Vec<SyntaxError>) {
+    let renderer = if std::io::stdout().is_terminal() {
+        Renderer::coloured()
+    } else {
+        Renderer::plain()
+    };
+    let source = sources.get(id);
+    for error in errors {
+        let diagnostic = Diagnostic::from_parser_error(&error, source);
+        print!("{}", renderer.render(&diagnostic, source));
+    }
+}
+
+/// Appends whatever the stream read since the last call to its source.
+fn sync_source<R: Read>(sources: &mut SourceMap, id: SourceId, stream: &StreamBuffer<R>) {
+    let source = sources.get_mut(id);
+    let read = stream.read_since(source.text().len());
+    source.push_str(&read);
+}
+
+fn toggle_trace() {
+    if trace::is_enabled() {
+        trace::disable();
+        println!("parser tracing off");
+    } else {
+        trace::enable();
+        println!("parser tracing on");
+    }
+}
+
+fn print_trace() {
+    for node in trace::take() {
+        print!("{}", node);
+    }
+}
+
+/// The definitions made so far, which later inputs may refer to.
+#[derive(Default)]
+struct Session {
+    definitions: Vec<Definition>,
+    /// Whether to substitute the definitions in before evaluating, rather than
+    /// unfolding them by name as they are applied.
+    inline: bool,
+    /// How number literals are expanded and read back.
+    numerals: NumeralEncoding,
+}
+
+impl Session {
+    fn toggle_inline(&mut self) {
+        self.inline = !self.inline;
+        if self.inline {
+            println!("inlining definitions");
+        } else {
+            println!("unfolding definitions by name");
         }
-        let line = line.unwrap();
-        let buffer = PositionedBuffer::new(line.as_str());
-        let parsed = Expression::parse(buffer);
-        if parsed.is_err() {
-            print_error();
-            continue;
+    }
+
+    fn set_numerals<R: Read>(&mut self, stream: &mut StreamBuffer<R>, encoding: &str) {
+        match encoding.parse() {
+            Ok(numerals) => {
+                self.numerals = numerals;
+                stream.set_numerals(numerals);
+                println!("{} numerals", numerals);
+            }
+            Err(()) => println!(
+                "!! unknown encoding {}, try church, scott, binary or unary",
+                encoding
+            ),
         }
-        let parsed = parsed.unwrap();
-        if parsed.1.buffer.len() > 1 {
-            print_error();
-            continue;
+    }
+
+    fn print_doc(&self, name: &str) {
+        let program = Program {
+            definitions: self.definitions.clone(),
+            main: None,
+        };
+        match program.definition(name) {
+            Some(Definition { doc: Some(doc), .. }) => println!("{}", doc),
+            Some(_) => println!("{} is not documented", name),
+            None => println!("!! {} is not defined", name),
         }
-        let mut expression = parsed.0;
-        DeBruijnConverter::convert(&mut expression);
-        let format = ExpressionPrettyPrinter::format_named(&mut expression);
-        let mut term = UntypedLambdaTerm::new(expression);
-        let result = FullBetaEvaluator::trace(&mut term);
+    }
+
+    fn run(&mut self, program: Program) {
+        for definition in &program.definitions {
+            println!("{} defined", definition.name.symbol);
+        }
+        self.definitions.extend(program.definitions);
+        let Some(main) = program.main else {
+            return;
+        };
+
+        let mut program = Program {
+            definitions: self.definitions.clone(),
+            main: Some(main),
+        };
+        DeBruijnConverter::convert_program(&mut program);
+        let numerals = self.numerals;
+        let readback =
+            |expression: &_| ExpressionPrettyPrinter::format_readback(expression, numerals);
+        let (format, result) = if self.inline {
+            let expression = program.inline().unwrap();
+            let format = readback(&expression);
+            let mut term = UntypedLambdaTerm::new(expression);
+            (format, FullBetaEvaluator::trace_with(&mut term, readback))
+        } else {
+            let mut term = UntypedProgramTerm::new(program).unwrap();
+            let format = readback(term.as_expr());
+            (format, FullBetaEvaluator::trace_with(&mut term, readback))
+        };
         if result.is_empty() {
             println!("stuck!");
         }
@@ -49,7 +141,93 @@ fn main() -> Result<(), std::io::Error> {
         for (i, step) in result.iter().enumerate() {
             println!("{}. {}", i + 1, step);
         }
-        print_prompt();
     }
-    Ok(())
+}
+
+/// Runs every definition and expression of a program piped into stdin.
+fn run_program<R: Read>(
+    session: &mut Session,
+    sources: &mut SourceMap,
+    id: SourceId,
+    mut stream: StreamBuffer<R>,
+) -> Result<(), StreamError> {
+    loop {
+        let result = stream.next_program();
+        sync_source(sources, id, &stream);
+        match result {
+            Ok(Some(program)) => session.run(program),
+            Ok(None) => return Ok(()),
+            Err(StreamError::Syntax(errors)) => print_syntax_errors(sources, id, errors),
+            Err(error) => return Err(error),
+        }
+    }
+}
+
+fn main() -> Result<(), StreamError> {
+    let mut session = Session::default();
+    let mut sources = SourceMap::new();
+    let stdin_id = sources.add(Source::new("<stdin>", ""));
+    let stdin = std::io::stdin();
+    if !stdin.is_terminal() {
+        let stream = StreamBuffer::new(stdin.lock());
+        return run_program(&mut session, &mut sources, stdin_id, stream);
+    }
+
+    let mut stream = StreamBuffer::new(stdin.lock());
+    print_prompt();
+    loop {
+        let read = stream.read_line();
+        sync_source(&mut sources, stdin_id, &stream);
+        if let Err(error) = read {
+            println!("!! {}", error);
+            stream.clear();
+            print_prompt();
+            continue;
+        }
+        let command = stream.pending().trim().to_string();
+        if command == ":trace" {
+            stream.clear();
+            toggle_trace();
+            print_prompt();
+            continue;
+        }
+        if command == ":inline" {
+            stream.clear();
+            session.toggle_inline();
+            print_prompt();
+            continue;
+        }
+        if let Some(encoding) = command.strip_prefix(":numerals ") {
+            stream.clear();
+            session.set_numerals(&mut stream, encoding.trim());
+            print_prompt();
+            continue;
+        }
+        if let Some(name) = command.strip_prefix(":doc ") {
+            stream.clear();
+            session.print_doc(name.trim());
+            print_prompt();
+            continue;
+        }
+        loop {
+            let result = stream.try_parse_program();
+            print_trace();
+            match result {
+                Ok(Some(program)) => session.run(program),
+                Ok(None) if stream.is_exhausted() => return Ok(()),
+                Ok(None) => break,
+                Err(StreamError::Incomplete) => break,
+                Err(StreamError::Syntax(errors)) => print_syntax_errors(&sources, stdin_id, errors),
+                Err(error) => {
+                    println!("!! {}", error);
+                    break;
+                }
+            }
+        }
+        if stream.pending().trim().is_empty() {
+            print_prompt();
+        } else {
+            print_continuation_prompt();
+        }
+    }
 }
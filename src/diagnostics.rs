@@ -0,0 +1,286 @@
+use std::fmt::{Display, Formatter, Write};
+use std::ops::Range;
+
+use f_prime_parser::ParserError;
+
+use crate::expression::source::Source;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+impl Display for Severity {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Severity::Error => write!(f, "error"),
+            Severity::Warning => write!(f, "warning"),
+        }
+    }
+}
+
+/// A problem found in a source, pointing at the range of text it is about.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub message: String,
+    pub span: Range<usize>,
+    /// Shown next to the underlined span.
+    pub label: Option<String>,
+    pub notes: Vec<String>,
+}
+
+impl Diagnostic {
+    pub fn new(severity: Severity, message: impl Into<String>, span: Range<usize>) -> Self {
+        Diagnostic {
+            severity,
+            message: message.into(),
+            span,
+            label: None,
+            notes: Vec::new(),
+        }
+    }
+
+    pub fn error(message: impl Into<String>, span: Range<usize>) -> Self {
+        Diagnostic::new(Severity::Error, message, span)
+    }
+
+    pub fn warning(message: impl Into<String>, span: Range<usize>) -> Self {
+        Diagnostic::new(Severity::Warning, message, span)
+    }
+
+    pub fn with_label(self, label: impl Into<String>) -> Self {
+        Diagnostic {
+            label: Some(label.into()),
+            ..self
+        }
+    }
+
+    pub fn with_note(mut self, note: impl Into<String>) -> Self {
+        self.notes.push(note.into());
+        self
+    }
+
+    /// Describes a syntax error in `source`, naming the unexpected input when there is no message.
+    pub fn from_parser_error<I>(error: &ParserError<I>, source: &Source) -> Self {
+        let mut expected = ParserError::new((), error.span.clone());
+        expected.expected = error.expected.clone();
+        let expected = (!expected.expected.is_empty()).then(|| expected.to_string());
+
+        match &error.message {
+            Some(message) => {
+                let diagnostic = Diagnostic::error(message, error.span.clone());
+                match expected {
+                    Some(expected) => diagnostic.with_note(expected),
+                    None => diagnostic,
+                }
+            }
+            None => {
+                let found = match source.slice(error.span.clone()) {
+                    "" => "end of input".to_string(),
+                    found => format!("'{}'", escape_control(found)),
+                };
+                let diagnostic =
+                    Diagnostic::error(format!("unexpected {found}"), error.span.clone());
+                match expected {
+                    Some(expected) => diagnostic.with_label(expected),
+                    None => diagnostic,
+                }
+            }
+        }
+    }
+}
+
+/// Escapes the control characters of a text quoted in a message, such as line breaks.
+fn escape_control(text: &str) -> String {
+    text.chars()
+        .map(|c| {
+            if c.is_control() {
+                c.escape_default().to_string()
+            } else {
+                c.to_string()
+            }
+        })
+        .collect()
+}
+
+const RESET: &str = "\x1b[0m";
+const BOLD: &str = "\x1b[1m";
+const RED: &str = "\x1b[1;31m";
+const YELLOW: &str = "\x1b[1;33m";
+const BLUE: &str = "\x1b[1;34m";
+
+/// Renders diagnostics as reports quoting the offending line of the source, e.g.
+///
+/// ```text
+/// error: unexpected ')'
+///  --> <stdin>:1:3
+///   |
+/// 1 | f ) x
+///   |   ^ expected end of input
+/// ```
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Renderer {
+    colour: bool,
+}
+
+impl Renderer {
+    /// Renders without colours, e.g. for tests and output that is not a terminal.
+    pub fn plain() -> Self {
+        Renderer { colour: false }
+    }
+
+    pub fn coloured() -> Self {
+        Renderer { colour: true }
+    }
+
+    fn paint(&self, style: &'static str) -> &'static str {
+        if self.colour {
+            style
+        } else {
+            ""
+        }
+    }
+
+    pub fn render(&self, diagnostic: &Diagnostic, source: &Source) -> String {
+        let (bold, blue, reset) = (self.paint(BOLD), self.paint(BLUE), self.paint(RESET));
+        let accent = match diagnostic.severity {
+            Severity::Error => self.paint(RED),
+            Severity::Warning => self.paint(YELLOW),
+        };
+
+        let location = source.location(diagnostic.span.start);
+        let line = source.line(location.line).unwrap_or_default();
+        let gutter = " ".repeat(location.line.to_string().len());
+
+        let underlined = source.slice(diagnostic.span.clone());
+        let width = underlined
+            .split('\n')
+            .next()
+            .unwrap_or_default()
+            .chars()
+            .count()
+            .max(1);
+        let padding = " ".repeat(location.column - 1);
+        let underline = "^".repeat(width);
+
+        let mut report = String::new();
+        let severity = diagnostic.severity;
+        let message = &diagnostic.message;
+        let _ = writeln!(report, "{accent}{severity}{reset}{bold}: {message}{reset}");
+        let _ = writeln!(
+            report,
+            "{gutter}{blue}-->{reset} {}:{location}",
+            source.name
+        );
+        let _ = writeln!(report, "{gutter} {blue}|{reset}");
+        let quoted = format!("{blue}{}{reset} {blue}|{reset} {line}", location.line);
+        let _ = writeln!(report, "{}", quoted.trim_end());
+        let _ = write!(
+            report,
+            "{gutter} {blue}|{reset} {padding}{accent}{underline}"
+        );
+        if let Some(label) = &diagnostic.label {
+            let _ = write!(report, " {label}");
+        }
+        let _ = writeln!(report, "{reset}");
+        for note in &diagnostic.notes {
+            let _ = writeln!(report, "{gutter} {blue}={reset} {bold}note{reset}: {note}");
+        }
+        report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::expression::buffer::Parsable;
+    use crate::expression::Expression;
+
+    fn render(source: &Source) -> String {
+        let error = Expression::parse_complete(source.buffer()).unwrap_err();
+        let diagnostic = Diagnostic::from_parser_error(&error, source);
+        Renderer::plain().render(&diagnostic, source)
+    }
+
+    #[test]
+    fn test_render_unexpected() {
+        let source = Source::new("<stdin>", "f ) x");
+        assert_eq!(
+            render(&source),
+            "\
+error: unexpected ')'
+ --> <stdin>:1:3
+  |
+1 | f ) x
+  |   ^ expected end of input
+"
+        );
+    }
+
+    #[test]
+    fn test_render_multibyte() {
+        let source = Source::new("<stdin>", "f λx\ny");
+        let error = ParserError::new((), 2..3).with_expected("a variable");
+        let diagnostic = Diagnostic::from_parser_error(&error, &source);
+        assert_eq!(
+            Renderer::plain().render(&diagnostic, &source),
+            "\
+error: unexpected 'λ'
+ --> <stdin>:1:3
+  |
+1 | f λx
+  |   ^ expected a variable
+"
+        );
+
+        let error = ParserError::new((), 4..6).with_expected("'.'");
+        let diagnostic = Diagnostic::from_parser_error(&error, &source);
+        assert_eq!(diagnostic.message, "unexpected 'x\\n'");
+        assert!(Renderer::plain()
+            .render(&diagnostic, &source)
+            .ends_with("|    ^ expected '.'\n"));
+    }
+
+    #[test]
+    fn test_render_end_of_input() {
+        let source = Source::new("test.lam", "-- identity\n\nλx.\n");
+        assert_eq!(
+            render(&source),
+            "\
+error: unexpected end of input
+ --> test.lam:4:1
+  |
+4 |
+  | ^ expected '(', a let expression, a number, a variable or an abstraction
+"
+        );
+    }
+
+    #[test]
+    fn test_render_message() {
+        let source = Source::new("test.lam", "λλ. x {- open");
+        let error = ParserError::new((), 8..10)
+            .with_message("unterminated block comment")
+            .with_expected("'-}'");
+        let diagnostic = Diagnostic::from_parser_error(&error, &source)
+            .with_label("opened here")
+            .with_note("block comments nest");
+        assert_eq!(
+            Renderer::plain().render(&diagnostic, &source),
+            "\
+error: unterminated block comment
+ --> test.lam:1:7
+  |
+1 | λλ. x {- open
+  |       ^^ opened here
+  = note: expected '-}'
+  = note: block comments nest
+"
+        );
+        assert!(Renderer::coloured()
+            .render(&diagnostic, &source)
+            .starts_with("\x1b[1;31merror\x1b[0m"));
+    }
+}
@@ -1,6 +1,9 @@
+use crate::expression::Expression;
 use crate::term::Term;
 use crate::traverse::pretty_print::ExpressionPrettyPrinter;
 
+pub use crate::lang::arith::eval as arith;
+
 pub mod by_value;
 pub mod full;
 
@@ -26,6 +29,9 @@ where
     fn trace_once(term: &mut T) -> Option<String>;
 
     fn trace(term: &mut T) -> Vec<String>;
+
+    /// Traces like `trace`, formatting each step with `format`.
+    fn trace_with(term: &mut T, format: impl Fn(&Expression) -> String) -> Vec<String>;
 }
 
 impl<T, E> TracingBetaReduction<T> for E
@@ -42,9 +48,13 @@ where
     }
 
     fn trace(term: &mut T) -> Vec<String> {
+        Self::trace_with(term, ExpressionPrettyPrinter::format)
+    }
+
+    fn trace_with(term: &mut T, format: impl Fn(&Expression) -> String) -> Vec<String> {
         let mut trace = Vec::new();
-        while let Some(string) = Self::trace_once(term) {
-            trace.push(string);
+        while Self::reduce_once(term) {
+            trace.push(format(term.as_expr()));
         }
         trace
     }
@@ -1,15 +1,23 @@
 use crate::eval::BetaReduction;
 use crate::expression::abstraction::{Abstraction, TypedAbstraction};
-use crate::expression::variable::Variable;
+use crate::expression::binding::Let;
+use crate::expression::variable::{DeBruijnIndex, Variable};
 use crate::expression::Expression;
-use crate::term::untyped::UntypedLambdaTerm;
-use crate::term::Term;
+use crate::term::{DeltaRule, Term};
 use crate::traverse::de_bruijn::shift::DeBruijnShift;
 use crate::traverse::de_bruijn::substitution::DeBruijnSubstitution;
 
 #[derive(Default)]
 pub struct CallByValueEvaluator {
     normalize: bool,
+    /// The number of abstractions and undecided conditionals around the expression being traversed.
+    /// Fixed points are not unfolded under them, where normalizing may never stop.
+    binders: usize,
+    /// Whether fixed points applied to closed terms are unfolded under abstractions as well.
+    /// Normalizing only does so once no other redex is left, so that the branches a recursive
+    /// definition discards are never unfolded.
+    unfolding: bool,
+    delta: Option<DeltaRule>,
 }
 
 impl CallByValueEvaluator {
@@ -19,17 +27,188 @@ impl CallByValueEvaluator {
     }
 
     pub fn normalize(expression: &mut Expression) -> bool {
-        let mut evaluator = Self { normalize: true };
+        let evaluator = Self {
+            normalize: true,
+            ..Self::default()
+        };
+        evaluator.normalize_once(expression)
+    }
+
+    /// Evaluates like `evaluate`, also reducing constants applied to values with `delta`.
+    pub fn evaluate_with(expression: &mut Expression, delta: DeltaRule) -> bool {
+        let mut evaluator = Self {
+            delta: Some(delta),
+            ..Self::default()
+        };
         evaluator.traverse(expression)
     }
 
+    /// Normalizes like `normalize`, also reducing constants applied to normal forms with `delta`.
+    pub fn normalize_with(expression: &mut Expression, delta: DeltaRule) -> bool {
+        let evaluator = Self {
+            normalize: true,
+            delta: Some(delta),
+            ..Self::default()
+        };
+        evaluator.normalize_once(expression)
+    }
+
+    /// Takes a step of normalization, unfolding a fixed point under an abstraction
+    /// only if there is nothing else to reduce, see `unfolding`.
+    fn normalize_once(mut self, expression: &mut Expression) -> bool {
+        if self.traverse(expression) {
+            return true;
+        }
+        self.unfolding = true;
+        self.traverse(expression)
+    }
+
+    /// Applies the delta rule to an application of a constant to all its arguments.
+    fn reduce_constant(&self, expression: &mut Expression) -> bool {
+        let Some(delta) = self.delta else {
+            return false;
+        };
+        let mut arguments = Vec::new();
+        let mut head = &*expression;
+        while let Expression::Application(application) = head {
+            arguments.push(application.argument.clone());
+            head = &application.applicator;
+        }
+        let Expression::Constant(constant) = head else {
+            return false;
+        };
+        arguments.reverse();
+        match delta(constant, &arguments) {
+            Some(result) => {
+                *expression = result;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Substitutes the value for the variable bound around the body, which is then unbound.
+    fn instantiate(mut body: Expression, mut value: Expression) -> Expression {
+        let target = 1;
+        DeBruijnShift::shift(1, &mut value);
+        DeBruijnSubstitution::substitute(target, value, &mut body);
+        DeBruijnShift::shift(-1, &mut body);
+        body
+    }
+
+    fn traverse_binder(&mut self, body: &mut Expression) -> bool {
+        self.binders += 1;
+        let reduced = self.traverse(body);
+        self.binders -= 1;
+        reduced
+    }
+
+    /// Whether the expression has no free variables, `depth` binders below the traversed one.
+    fn is_closed(expression: &Expression, depth: DeBruijnIndex) -> bool {
+        match expression {
+            Expression::Variable(variable) => variable.index <= depth,
+            Expression::Abstraction(box Abstraction {
+                parameter: _, body, ..
+            })
+            | Expression::TypedAbstraction(box TypedAbstraction {
+                parameter: _, body, ..
+            }) => Self::is_closed(body, depth + 1),
+            Expression::Application(application) => {
+                Self::is_closed(&application.applicator, depth)
+                    && Self::is_closed(&application.argument, depth)
+            }
+            Expression::Let(binding) => {
+                Self::is_closed(&binding.value, depth + binding.recursive as DeBruijnIndex)
+                    && Self::is_closed(&binding.body, depth + 1)
+            }
+            Expression::Conditional(conditional) => [
+                &conditional.condition,
+                &conditional.consequent,
+                &conditional.alternative,
+            ]
+            .into_iter()
+            .all(|expression| Self::is_closed(expression, depth)),
+            Expression::Tuple(tuple) => tuple
+                .elements
+                .iter()
+                .all(|element| Self::is_closed(element, depth)),
+            Expression::Projection(projection) => Self::is_closed(&projection.tuple, depth),
+            Expression::Constant(_) | Expression::Error(_) => true,
+        }
+    }
+
     fn traverse(&mut self, expression: &mut Expression) -> bool {
         match expression {
-            Expression::Variable(_) => false,
-            Expression::Abstraction(box Abstraction { parameter: _, body })
+            Expression::Variable(_) | Expression::Constant(_) | Expression::Error(_) => false,
+            Expression::Abstraction(box Abstraction {
+                parameter: _, body, ..
+            })
             | Expression::TypedAbstraction(box TypedAbstraction {
                 parameter: _, body, ..
-            }) => self.normalize && self.traverse(body),
+            }) => self.normalize && self.traverse_binder(body),
+            Expression::Conditional(conditional) => {
+                if (self.normalize || !conditional.condition.is_value())
+                    && self.traverse(&mut conditional.condition)
+                {
+                    return true;
+                }
+                if let Some(branch) = conditional.branch().cloned() {
+                    *expression = branch;
+                    return true;
+                }
+                // Like abstraction bodies, the branches of a stuck condition may never be taken.
+                self.normalize
+                    && (self.traverse_binder(&mut conditional.consequent)
+                        || self.traverse_binder(&mut conditional.alternative))
+            }
+            Expression::Tuple(tuple) => tuple
+                .elements
+                .iter_mut()
+                .any(|element| (self.normalize || !element.is_value()) && self.traverse(element)),
+            Expression::Projection(projection) => {
+                if (self.normalize || !projection.tuple.is_value())
+                    && self.traverse(&mut projection.tuple)
+                {
+                    return true;
+                }
+                if !self.normalize && !projection.tuple.is_value() {
+                    return false;
+                }
+                match projection.element() {
+                    Some(element) => {
+                        *expression = element.clone();
+                        true
+                    }
+                    None => false,
+                }
+            }
+            Expression::Let(binding) if binding.fixpoint => {
+                self.normalize && self.traverse_binder(&mut binding.value)
+            }
+            Expression::Let(binding) if binding.recursive => {
+                let dummy = Expression::from(Variable::from(String::new()));
+                if let Expression::Let(binding) = std::mem::replace(expression, dummy) {
+                    let fixpoint = Let::fixpoint(binding.variable, binding.value);
+                    *expression = Self::instantiate(binding.body, Expression::from(fixpoint));
+                    true
+                } else {
+                    unreachable!()
+                }
+            }
+            Expression::Let(binding) => {
+                if (self.normalize || !binding.value.is_value())
+                    && self.traverse(&mut binding.value)
+                {
+                    return true;
+                }
+                let dummy = Expression::from(Variable::from(String::new()));
+                if let Expression::Let(binding) = std::mem::replace(expression, dummy) {
+                    *expression = Self::instantiate(binding.body, binding.value);
+                    true
+                } else {
+                    unreachable!()
+                }
+            }
             Expression::Application(application) => {
                 if (self.normalize || !application.applicator.is_value())
                     && self.traverse(&mut application.applicator)
@@ -41,8 +220,18 @@ impl CallByValueEvaluator {
                 {
                     return true;
                 }
+                if let Expression::Let(fixpoint) = &application.applicator {
+                    let unfolding = self.unfolding && Self::is_closed(&application.argument, 0);
+                    if !fixpoint.fixpoint || self.binders > 0 && !unfolding {
+                        return false;
+                    }
+                    let fixpoint = fixpoint.clone();
+                    let value = fixpoint.value.clone();
+                    application.applicator = Self::instantiate(value, Expression::Let(fixpoint));
+                    return true;
+                }
                 if !matches!(application.applicator, Expression::Abstraction(_)) {
-                    return false;
+                    return self.reduce_constant(expression);
                 }
                 let dummy = Expression::from(Variable::from(String::new()));
                 let application = std::mem::replace(expression, dummy);
@@ -53,17 +242,9 @@ impl CallByValueEvaluator {
                     if self.traverse(&mut application.argument) {
                         return true;
                     }
-                    if let Expression::Abstraction(mut applicator) = application.applicator {
-                        let target = 1;
-                        DeBruijnShift::shift(1, &mut application.argument);
-                        DeBruijnSubstitution::substitute(
-                            target,
-                            application.argument,
-                            &mut applicator.body,
-                        );
-                        DeBruijnShift::shift(-1, &mut applicator.body);
-                        *expression = applicator.body;
-                        return true;
+                    if let Expression::Abstraction(applicator) = application.applicator {
+                        *expression = Self::instantiate(applicator.body, application.argument);
+                        true
                     } else {
                         unreachable!()
                     }
@@ -75,8 +256,200 @@ impl CallByValueEvaluator {
     }
 }
 
-impl BetaReduction<UntypedLambdaTerm> for CallByValueEvaluator {
-    fn reduce_once(term: &mut UntypedLambdaTerm) -> bool {
-        Self::evaluate(term.as_expr_mut())
+impl<T: Term> BetaReduction<T> for CallByValueEvaluator {
+    fn reduce_once(term: &mut T) -> bool {
+        Self::evaluate_with(term.as_expr_mut(), T::delta)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::eval::full::FullBetaEvaluator;
+    use crate::eval::TracingBetaReduction;
+    use crate::expression::buffer::{Parsable, PositionedBuffer};
+    use crate::expression::constant::{Constant, DefinedConstants};
+    use crate::term::untyped::UntypedLambdaTerm;
+    use crate::traverse::de_bruijn::convert::DeBruijnConverter;
+    use crate::traverse::pretty_print::ExpressionPrettyPrinter;
+
+    use super::*;
+
+    /// Doubles a Scott numeral, which is either `zero` or `succ n`.
+    const DOUBLE: &str = "\
+        let zero = λz s. z, succ = λn z s. s n in
+        letrec double = λn. n zero (λm. succ (succ (double m))) in
+        double (succ zero)";
+
+    fn term(source: &str) -> UntypedLambdaTerm {
+        let (mut expression, _) =
+            Expression::parse_complete(PositionedBuffer::new(source)).unwrap();
+        DeBruijnConverter::convert(&mut expression);
+        UntypedLambdaTerm::new(expression)
+    }
+
+    #[test]
+    fn test_letrec() {
+        let mut term = term(DOUBLE);
+        assert!(CallByValueEvaluator::reduce(&mut term));
+        assert_eq!(
+            ExpressionPrettyPrinter::format_named(term.as_expr()),
+            "λz. λs. s (λz. λs. s (λz. λs. z))"
+        );
+    }
+
+    #[test]
+    fn test_letrec_fixpoint() {
+        let mut term = term("letrec f = λx. x in f");
+        assert!(!term.as_expr().is_value());
+        assert!(CallByValueEvaluator::reduce(&mut term));
+        assert!(term.as_expr().is_value());
+    }
+
+    #[test]
+    fn test_letrec_normalize() {
+        let mut term = self::term(DOUBLE);
+        assert!(FullBetaEvaluator::reduce(&mut term));
+        assert_eq!(
+            ExpressionPrettyPrinter::format_indexed(term.as_expr()),
+            "λ λ 1 (λ λ 1 (λ λ 2))"
+        );
+
+        let mut term = self::term("letrec f = λx. f x in λy. f y");
+        assert!(FullBetaEvaluator::reduce(&mut term));
+        assert_eq!(
+            ExpressionPrettyPrinter::format_named(term.as_expr()),
+            "λy. (letrec f = λx. f x in f) y"
+        );
+    }
+
+    #[test]
+    fn test_tuple() {
+        let mut term = term("(λp. (p.2, p.1)) ((λx. x) (λz. z), λy. y)");
+        let trace = CallByValueEvaluator::trace(&mut term);
+        assert_eq!(
+            trace,
+            vec![
+                "(λp. (p.2, p.1)) (λz. z, λy. y)",
+                "((λz. z, λy. y).2, (λz. z, λy. y).1)",
+                "(λy. y, (λz. z, λy. y).1)",
+                "(λy. y, λz. z)"
+            ]
+        );
+        assert!(term.as_expr().is_value());
+
+        let mut term = self::term("(λx. x, λy. y).3");
+        assert!(!CallByValueEvaluator::reduce(&mut term));
+
+        let mut term = self::term("(λx. x, (λy. y) (λz. z)).1");
+        let trace = CallByValueEvaluator::trace(&mut term);
+        assert_eq!(trace, vec!["(λx. x, λz. z).1", "λx. x"]);
+
+        let mut term = self::term("(λx. x, f z).1");
+        assert!(!CallByValueEvaluator::reduce(&mut term));
+        assert!(FullBetaEvaluator::reduce(&mut term));
+        assert_eq!(
+            ExpressionPrettyPrinter::format_named(term.as_expr()),
+            "λx. x"
+        );
+    }
+
+    /// Booleans as constants, with `not` as their only operation.
+    struct BooleanTerm {
+        expression: Expression,
+    }
+
+    impl DefinedConstants for BooleanTerm {
+        const CHOICES: &'static [&'static str] = &["true", "false", "not"];
+    }
+
+    impl Term for BooleanTerm {
+        fn as_expr(&self) -> &Expression {
+            &self.expression
+        }
+
+        fn as_expr_mut(&mut self) -> &mut Expression {
+            &mut self.expression
+        }
+
+        fn validate(&self) -> bool {
+            true
+        }
+
+        fn delta(constant: &Constant, arguments: &[Expression]) -> Option<Expression> {
+            let [Expression::Constant(argument)] = arguments else {
+                return None;
+            };
+            let result = match (constant.symbol.as_str(), argument.symbol.as_str()) {
+                ("not", "true") => "false",
+                ("not", "false") => "true",
+                _ => return None,
+            };
+            Some(Expression::from(Constant::<()>::from(result.to_string())))
+        }
+    }
+
+    #[test]
+    fn test_conditional() {
+        let input = PositionedBuffer::new("(λb. if not b then b else not b) true")
+            .with_constants::<BooleanTerm>()
+            .with_conditionals();
+        let (mut expression, _) = Expression::parse_complete(input).unwrap();
+        DeBruijnConverter::convert(&mut expression);
+        let mut term = BooleanTerm { expression };
+        let trace = CallByValueEvaluator::trace(&mut term);
+        assert_eq!(
+            trace,
+            vec![
+                "if not true then true else not true",
+                "if false then true else not true",
+                "not true",
+                "false"
+            ]
+        );
+
+        let input = PositionedBuffer::new("λb. if b then (λx. x) b else not true")
+            .with_constants::<BooleanTerm>()
+            .with_conditionals();
+        let (mut expression, _) = Expression::parse_complete(input).unwrap();
+        DeBruijnConverter::convert(&mut expression);
+        let mut stuck = expression.clone();
+        assert!(!CallByValueEvaluator::evaluate_with(
+            &mut stuck,
+            BooleanTerm::delta
+        ));
+        while CallByValueEvaluator::normalize_with(&mut expression, BooleanTerm::delta) {}
+        assert_eq!(
+            ExpressionPrettyPrinter::format_named(&expression),
+            "λb. if b then b else false"
+        );
+    }
+
+    #[test]
+    fn test_delta() {
+        let input = PositionedBuffer::new("(λb. not (not b)) ((λx. x) true)")
+            .with_constants::<BooleanTerm>();
+        let (mut expression, _) = Expression::parse_complete(input).unwrap();
+        DeBruijnConverter::convert(&mut expression);
+        let mut term = BooleanTerm { expression };
+        let trace = CallByValueEvaluator::trace(&mut term);
+        assert_eq!(
+            trace,
+            vec![
+                "(λb. not (not b)) true",
+                "not (not true)",
+                "not false",
+                "true"
+            ]
+        );
+
+        let mut expression = term.expression.clone();
+        assert!(!CallByValueEvaluator::evaluate_with(
+            &mut expression,
+            BooleanTerm::delta
+        ));
+        let mut term = BooleanTerm {
+            expression: Expression::from(Constant::<()>::from("not".to_string())),
+        };
+        assert!(!CallByValueEvaluator::reduce(&mut term));
     }
 }
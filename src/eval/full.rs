@@ -1,24 +1,35 @@
 use crate::eval::by_value::CallByValueEvaluator;
 use crate::eval::BetaReduction;
-use crate::term::untyped::UntypedLambdaTerm;
+use crate::term::untyped::{UntypedLambdaTerm, UntypedProgramTerm};
 use crate::term::Term;
 
 pub struct FullBetaEvaluator;
 
 impl BetaReduction<UntypedLambdaTerm> for FullBetaEvaluator {
     fn reduce_once(term: &mut UntypedLambdaTerm) -> bool {
-        CallByValueEvaluator::normalize(term.as_expr_mut())
+        CallByValueEvaluator::normalize_with(term.as_expr_mut(), UntypedLambdaTerm::delta)
+    }
+}
+
+/// Reduces the expression as far as it goes before unfolding the next applied definition.
+impl BetaReduction<UntypedProgramTerm> for FullBetaEvaluator {
+    fn reduce_once(term: &mut UntypedProgramTerm) -> bool {
+        CallByValueEvaluator::normalize_with(term.as_expr_mut(), UntypedProgramTerm::delta)
+            || term.unfold()
     }
 }
 
 #[cfg(test)]
 mod tests {
     use crate::expression::buffer::{Parsable, PositionedBuffer};
+    use crate::expression::numeral::NumeralEncoding;
+    use crate::expression::program::Program;
     use crate::expression::Expression;
     use crate::traverse::de_bruijn::convert::DeBruijnConverter;
     use crate::traverse::pretty_print::ExpressionPrettyPrinter;
 
     use super::*;
+    use crate::eval::TracingBetaReduction;
 
     #[test]
     fn test_full_beta() {
@@ -32,4 +43,72 @@ mod tests {
         let format = ExpressionPrettyPrinter::format_named(term.as_expr_mut());
         assert_eq!(format, "λs. λz. s z");
     }
+
+    #[test]
+    fn test_full_beta_numerals() {
+        let input = PositionedBuffer::new("(λn.λs.λz.s (n s z)) 0");
+        let (mut expression, _) = Expression::parse(input).unwrap();
+        DeBruijnConverter::convert(&mut expression);
+        let mut term = UntypedLambdaTerm::new(expression);
+        let trace = FullBetaEvaluator::trace_with(&mut term, |expression| {
+            ExpressionPrettyPrinter::format_readback(expression, NumeralEncoding::Church)
+        });
+        assert_eq!(trace.last().unwrap(), "1");
+
+        let input = PositionedBuffer::new("(λn. n (λz s. z) (λp. p)) 2")
+            .with_numerals(NumeralEncoding::Scott);
+        let (mut expression, _) = Expression::parse(input).unwrap();
+        DeBruijnConverter::convert(&mut expression);
+        let mut term = UntypedLambdaTerm::new(expression);
+        FullBetaEvaluator::reduce(&mut term);
+        let format =
+            ExpressionPrettyPrinter::format_readback(term.as_expr(), NumeralEncoding::Scott);
+        assert_eq!(format, "1");
+    }
+
+    #[test]
+    fn test_full_beta_let() {
+        let input = PositionedBuffer::new("let twice = λf x. f (f x), id = λx. x in twice id y");
+        let (mut expression, _) = Expression::parse_complete(input).unwrap();
+        DeBruijnConverter::convert(&mut expression);
+        let mut term = UntypedLambdaTerm::new(expression);
+        let trace = FullBetaEvaluator::trace(&mut term);
+        assert_eq!(
+            trace.first().unwrap(),
+            "let id = λx. x in (λf. λx. f (f x)) id y"
+        );
+        assert_eq!(trace.last().unwrap(), "y");
+    }
+
+    #[test]
+    fn test_full_beta_tuple() {
+        let input = PositionedBuffer::new("λx. (λp. (p.2, p.1)) (x, (λy. y) x)");
+        let (mut expression, _) = Expression::parse_complete(input).unwrap();
+        DeBruijnConverter::convert(&mut expression);
+        let mut term = UntypedLambdaTerm::new(expression);
+        FullBetaEvaluator::reduce(&mut term);
+        assert_eq!(
+            ExpressionPrettyPrinter::format_named(term.as_expr()),
+            "λx. (x, x)"
+        );
+    }
+
+    #[test]
+    fn test_full_beta_program() {
+        let input = PositionedBuffer::new(
+            "id = λx. x;\ntwice = λf x. f (f x);\nconst = λx y. x;\ntwice id (λz. z const)",
+        );
+        let (mut program, _) = Program::parse_complete(input).unwrap();
+        DeBruijnConverter::convert_program(&mut program);
+        let mut term = UntypedProgramTerm::new(program).unwrap();
+        assert!(FullBetaEvaluator::reduce(&mut term));
+        assert_eq!(
+            ExpressionPrettyPrinter::format_named(term.as_expr()),
+            "λz. z const"
+        );
+        assert_eq!(
+            ExpressionPrettyPrinter::format_indexed(term.as_expr()),
+            "λ 1 2"
+        );
+    }
 }
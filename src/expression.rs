@@ -1,56 +1,347 @@
-use f_prime_parser::combinators::between;
-use f_prime_parser::{Parser, ParserResult};
+use std::collections::BTreeSet;
+
+use f_prime_parser::combinators::spanned;
+use f_prime_parser::memo::Relocatable;
+use f_prime_parser::{Parser, ParserError, ParserInput, ParserResult, ThenParserExtensions};
 
 use crate::expression::abstraction::{Abstraction, TypedAbstraction};
 use crate::expression::application::Application;
-use crate::expression::buffer::{Parsable, PositionedBuffer};
-use crate::expression::symbol::literal_parser;
+use crate::expression::binding::Let;
+use crate::expression::buffer::{
+    end_parser, skip_group_parser, skip_line_parser, Parsable, PositionedBuffer,
+};
+use crate::expression::conditional::Conditional;
+use crate::expression::constant::{parse_constant, Constant};
+use crate::expression::numeral::MAX_NUMERAL;
+use crate::expression::source::Span;
+use crate::expression::symbol::{keyword_parser, number_parser, parse_literal};
+use crate::expression::tuple::{Projection, Tuple};
 use crate::expression::variable::Variable;
 
 pub mod abstraction;
 pub mod application;
+pub mod binding;
 pub mod buffer;
+pub mod conditional;
 pub mod constant;
+pub mod incremental;
+pub mod lexer;
+pub mod numeral;
+pub mod program;
+pub mod source;
+pub mod stream;
 pub mod symbol;
+pub mod tuple;
 pub mod variable;
 
+pub type SyntaxError = ParserError<()>;
+
+/// A malformed part of the input, which parsing went on past.
+/// The range of the syntax error found is kept as a span, which equality ignores.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ErrorNode {
+    pub expected: BTreeSet<String>,
+    pub message: Option<String>,
+    pub fatal: bool,
+    pub span: Span,
+}
+
+impl ErrorNode {
+    /// The syntax error found in the malformed part.
+    pub fn error(&self) -> SyntaxError {
+        SyntaxError {
+            input: (),
+            span: self.span.range().unwrap_or_default(),
+            expected: self.expected.clone(),
+            message: self.message.clone(),
+            fatal: self.fatal,
+        }
+    }
+}
+
+impl<I> From<ParserError<I>> for ErrorNode {
+    fn from(value: ParserError<I>) -> Self {
+        ErrorNode {
+            expected: value.expected,
+            message: value.message,
+            fatal: value.fatal,
+            span: Span::new(value.span),
+        }
+    }
+}
+
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub enum Expression {
     Variable(Variable),
     Abstraction(Box<Abstraction>),
     TypedAbstraction(Box<TypedAbstraction>),
     Application(Box<Application>),
+    Let(Box<Let>),
+    Constant(Constant),
+    Conditional(Box<Conditional>),
+    Tuple(Box<Tuple>),
+    Projection(Box<Projection>),
+    Error(Box<ErrorNode>),
 }
 
 impl Expression {
     pub fn is_value(&self) -> bool {
-        matches!(self, Expression::Abstraction(_))
+        match self {
+            Expression::Abstraction(_) | Expression::Constant(_) => true,
+            Expression::Let(binding) => binding.fixpoint,
+            Expression::Tuple(tuple) => tuple.elements.iter().all(Expression::is_value),
+            _ => false,
+        }
+    }
+
+    /// The source range this expression was parsed from, if known.
+    pub fn span(&self) -> Span {
+        match self {
+            Expression::Variable(variable) => variable.span.clone(),
+            Expression::Abstraction(abstraction) => abstraction.span.clone(),
+            Expression::TypedAbstraction(abstraction) => abstraction.span.clone(),
+            Expression::Application(application) => application.span.clone(),
+            Expression::Let(binding) => binding.span.clone(),
+            Expression::Constant(constant) => constant.span.clone(),
+            Expression::Conditional(conditional) => conditional.span.clone(),
+            Expression::Tuple(tuple) => tuple.span.clone(),
+            Expression::Projection(projection) => projection.span.clone(),
+            Expression::Error(error) => error.span.clone(),
+        }
+    }
+
+    /// Replaces the span of this node, leaving error nodes as they are.
+    pub fn with_span(mut self, span: impl Into<Span>) -> Self {
+        let span = span.into();
+        match &mut self {
+            Expression::Variable(variable) => variable.span = span,
+            Expression::Abstraction(abstraction) => abstraction.span = span,
+            Expression::TypedAbstraction(abstraction) => abstraction.span = span,
+            Expression::Application(application) => application.span = span,
+            Expression::Let(binding) => binding.span = span,
+            Expression::Constant(constant) => constant.span = span,
+            Expression::Conditional(conditional) => conditional.span = span,
+            Expression::Tuple(tuple) => tuple.span = span,
+            Expression::Projection(projection) => projection.span = span,
+            Expression::Error(_) => {}
+        }
+        self
+    }
+
+    pub fn from_error<I>(error: ParserError<I>) -> Self {
+        Expression::Error(Box::new(ErrorNode::from(error)))
+    }
+
+    /// The syntax errors of the error nodes in this expression, from left to right.
+    pub fn errors(&self) -> Vec<SyntaxError> {
+        fn collect(expression: &Expression, errors: &mut Vec<SyntaxError>) {
+            match expression {
+                Expression::Variable(_) | Expression::Constant(_) => {}
+                Expression::Abstraction(abstraction) => collect(&abstraction.body, errors),
+                Expression::TypedAbstraction(abstraction) => {
+                    collect(&abstraction.parameter_type, errors);
+                    collect(&abstraction.body, errors);
+                }
+                Expression::Application(application) => {
+                    collect(&application.applicator, errors);
+                    collect(&application.argument, errors);
+                }
+                Expression::Let(binding) => {
+                    collect(&binding.value, errors);
+                    collect(&binding.body, errors);
+                }
+                Expression::Conditional(conditional) => {
+                    collect(&conditional.condition, errors);
+                    collect(&conditional.consequent, errors);
+                    collect(&conditional.alternative, errors);
+                }
+                Expression::Tuple(tuple) => {
+                    for element in &tuple.elements {
+                        collect(element, errors);
+                    }
+                }
+                Expression::Projection(projection) => collect(&projection.tuple, errors),
+                Expression::Error(error) => errors.push(error.error()),
+            }
+        }
+
+        let mut errors = Vec::new();
+        collect(self, &mut errors);
+        errors
+    }
+
+    /// Parses the whole input, replacing malformed parts with error nodes instead of failing.
+    /// Returns the partial expression together with all the syntax errors found.
+    pub fn parse_recovering(input: PositionedBuffer) -> (Expression, Vec<SyntaxError>) {
+        let parser = Expression::parser().recover(skip_line_parser(), Expression::from_error);
+        let (expression, remaining) = match parser.parse(input.with_recovery()) {
+            Ok(parsed) => parsed,
+            Err(error) => {
+                let expression = Expression::from_error(error);
+                let errors = expression.errors();
+                return (expression, errors);
+            }
+        };
+        let mut errors = expression.errors();
+        if let Err(error) = end_parser().parse(remaining) {
+            errors.push(error.map_input(|_| ()));
+        }
+        (expression, errors)
     }
 }
 
 impl Expression {
+    /// Parses a variable, with the projections following it.
     fn variable_parser<'a>() -> impl Parser<PositionedBuffer<'a>, Output = Self> + 'a {
-        Variable::parser().map(Expression::from)
+        let parser = |input: PositionedBuffer<'a>| {
+            let (variable, remaining) = Variable::parse(input)?;
+            Projection::parse_postfix(Expression::from(variable), remaining)
+        };
+        parser.traced("variable")
+    }
+
+    /// Parses one of the constants of the input, see `PositionedBuffer::with_constants`.
+    fn constant_parser<'a>() -> impl Parser<PositionedBuffer<'a>, Output = Self> + 'a {
+        let parser = |input: PositionedBuffer<'a>| {
+            let constants = input.constants();
+            parse_constant(constants, input)
+        };
+        parser.map(Expression::from).traced("constant")
     }
 
     fn abstraction_parser<'a>() -> impl Parser<PositionedBuffer<'a>, Output = Self> + 'a {
-        Abstraction::parser()
-            .map(Expression::from)
-            .or_else(TypedAbstraction::parser().map(Expression::from))
+        let tail_parser = TypedAbstraction::tail_parser().or_else(Abstraction::tail_parser());
+        let parser = Abstraction::lambda_parser().skip_then(tail_parser.cut());
+        spanned(parser)
+            .map(|(expression, span)| expression.with_span(span))
+            .label("an abstraction")
+            .traced("abstraction")
+    }
+
+    fn let_parser<'a>() -> impl Parser<PositionedBuffer<'a>, Output = Self> + 'a {
+        let parser = keyword_parser("let")
+            .skip_then(Let::tail_parser(false).cut())
+            .or_else(keyword_parser("letrec").skip_then(Let::tail_parser(true).cut()));
+        spanned(parser)
+            .map(|(expression, span)| expression.with_span(span))
+            .label("a let expression")
+            .traced("let")
+    }
+
+    /// Parses a conditional if the input has them, see `PositionedBuffer::with_conditionals`.
+    fn conditional_parser<'a>() -> impl Parser<PositionedBuffer<'a>, Output = Self> + 'a {
+        let parser = keyword_parser("if").skip_then(Conditional::tail_parser().cut());
+        let parser = spanned(parser)
+            .map(|(expression, span)| expression.with_span(span))
+            .label("a conditional")
+            .traced("conditional");
+        move |input: PositionedBuffer<'a>| {
+            if !input.conditionals() {
+                let input = input.seek_trivia();
+                let span = input.span();
+                return Err(ParserError::new(input, span));
+            }
+            parser.parse(input)
+        }
+    }
+
+    /// Parses a number literal up to `MAX_NUMERAL`, expanded with the encoding of the input,
+    /// with the projections following it.
+    fn numeral_parser<'a>() -> impl Parser<PositionedBuffer<'a>, Output = Self> + 'a {
+        let parser = |input: PositionedBuffer<'a>| {
+            let encoding = input.numerals();
+            let ((number, span), remaining) = spanned(number_parser()).parse(input.clone())?;
+            if number > MAX_NUMERAL {
+                let error = ParserError::new(input.seek_trivia(), span)
+                    .with_message(format!("number literals are at most {MAX_NUMERAL}"))
+                    .with_fatal();
+                return Err(error);
+            }
+            Projection::parse_postfix(encoding.encode(number).with_span(span), remaining)
+        };
+        parser.traced("numeral")
     }
 
     fn application_parser<'a>() -> impl Parser<PositionedBuffer<'a>, Output = Self> + 'a {
-        Application::parser().map(Expression::from)
+        Application::parser()
+            .map(Expression::from)
+            .traced("application")
+    }
+
+    /// Parses a parenthesized expression, or a tuple if there are several, with the projections
+    /// following it. Written as a single step, since it is on the path of every nesting.
+    fn group_parser<'a>() -> impl Parser<PositionedBuffer<'a>, Output = Self> + 'a {
+        let elements = Tuple::elements_parser().recover(skip_group_parser(), |error| {
+            vec![Expression::from_error(error)]
+        });
+        move |input: PositionedBuffer<'a>| {
+            let (_, remaining) = parse_literal("(", input)?;
+            let start = remaining.position - 1;
+            let (elements, remaining) = elements.parse(remaining)?;
+            let group = Tuple::group(elements, start..remaining.position);
+            Projection::parse_postfix(group, remaining)
+        }
     }
 
     fn atom_parser<'a>() -> impl Parser<PositionedBuffer<'a>, Output = Self> + 'a {
-        between(
-            literal_parser("("),
-            Expression::parser(),
-            literal_parser(")"),
-        )
-        .or_else(Expression::abstraction_parser())
-        .or_else(Expression::variable_parser())
+        Expression::abstraction_parser()
+            .or_else(Expression::let_parser())
+            .or_else(Expression::conditional_parser())
+            .or_else(Expression::numeral_parser())
+            .or_else(Expression::constant_parser())
+            .or_else(Expression::variable_parser())
+            // Last, so that fewer alternatives are on the stack of every nesting.
+            .or_else(Expression::group_parser())
+            .memoize("atom")
+            .traced("atom")
+    }
+}
+
+impl Relocatable for Expression {
+    fn relocate(&mut self, offset: isize) {
+        match self {
+            Expression::Variable(variable) => variable.span.shift(offset),
+            Expression::Constant(constant) => constant.span.shift(offset),
+            Expression::Abstraction(abstraction) => {
+                abstraction.span.shift(offset);
+                abstraction.parameter.span.shift(offset);
+                abstraction.body.relocate(offset);
+            }
+            Expression::TypedAbstraction(abstraction) => {
+                abstraction.span.shift(offset);
+                abstraction.parameter.span.shift(offset);
+                abstraction.parameter_type.relocate(offset);
+                abstraction.body.relocate(offset);
+            }
+            Expression::Application(application) => {
+                application.span.shift(offset);
+                application.applicator.relocate(offset);
+                application.argument.relocate(offset);
+            }
+            Expression::Let(binding) => {
+                binding.span.shift(offset);
+                binding.variable.span.shift(offset);
+                binding.value.relocate(offset);
+                binding.body.relocate(offset);
+            }
+            Expression::Conditional(conditional) => {
+                conditional.span.shift(offset);
+                conditional.condition.relocate(offset);
+                conditional.consequent.relocate(offset);
+                conditional.alternative.relocate(offset);
+            }
+            Expression::Tuple(tuple) => {
+                tuple.span.shift(offset);
+                for element in &mut tuple.elements {
+                    element.relocate(offset);
+                }
+            }
+            Expression::Projection(projection) => {
+                projection.span.shift(offset);
+                projection.tuple.relocate(offset);
+            }
+            Expression::Error(error) => error.span.shift(offset),
+        }
     }
 }
 
@@ -78,20 +369,54 @@ impl From<Application> for Expression {
     }
 }
 
+impl From<Let> for Expression {
+    fn from(value: Let) -> Self {
+        Expression::Let(Box::from(value))
+    }
+}
+
+impl From<Conditional> for Expression {
+    fn from(value: Conditional) -> Self {
+        Expression::Conditional(Box::from(value))
+    }
+}
+
+impl From<Tuple> for Expression {
+    fn from(value: Tuple) -> Self {
+        Expression::Tuple(Box::from(value))
+    }
+}
+
+impl From<Projection> for Expression {
+    fn from(value: Projection) -> Self {
+        Expression::Projection(Box::from(value))
+    }
+}
+
 impl Parsable for Expression {
     fn parse(input: PositionedBuffer) -> ParserResult<PositionedBuffer, Self> {
         let parser = Expression::abstraction_parser()
+            .or_else(Expression::let_parser())
+            .or_else(Expression::conditional_parser())
             .or_else(Expression::application_parser())
-            .or_else(Expression::atom_parser());
+            .or_else(Expression::atom_parser())
+            .memoize("expression")
+            .traced("expression");
         parser.parse(input)
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use std::assert_matches::assert_matches;
+    use std::assert_matches;
+
+    use f_prime_parser::trace;
+    use f_prime_parser::trace::TraceOutcome;
 
     use super::*;
+    use crate::expression::constant::DefinedConstants;
+    use crate::expression::numeral::NumeralEncoding;
+    use crate::traverse::de_bruijn::convert::DeBruijnConverter;
 
     #[test]
     fn test_variable() {
@@ -164,4 +489,189 @@ mod tests {
         assert!(remaining.buffer.is_empty());
         assert_matches!(expression, Expression::Abstraction(_));
     }
+
+    #[test]
+    fn test_constant() {
+        struct Booleans;
+
+        impl DefinedConstants for Booleans {
+            const CHOICES: &'static [&'static str] = &["true", "false"];
+        }
+
+        let input = PositionedBuffer::new("f true x").with_constants::<Booleans>();
+        let (expression, _) = Expression::parse_complete(input).unwrap();
+        let application = Application::try_from(expression).unwrap();
+        assert_matches!(application.argument, Expression::Variable(_));
+        let application = Application::try_from(application.applicator).unwrap();
+        let constant = Constant::try_from(application.argument).unwrap();
+        assert_eq!(constant.symbol, "true");
+        assert_eq!(constant.span.range(), Some(2..6));
+
+        let input = PositionedBuffer::new("f true x");
+        let (expression, _) = Expression::parse_complete(input).unwrap();
+        assert_eq!(expression.errors(), vec![]);
+        assert_matches!(
+            Application::try_from(expression).unwrap().applicator,
+            Expression::Application(box Application {
+                argument: Expression::Variable(_),
+                ..
+            })
+        );
+
+        let input = PositionedBuffer::new("λtrue. x").with_constants::<Booleans>();
+        let error = Expression::parse(input).unwrap_err();
+        assert_eq!(error.to_string(), "'true' is a constant of the language");
+        assert_eq!(error.span, 2..3);
+    }
+
+    #[test]
+    fn test_numeral_limit() {
+        let source = format!("f {MAX_NUMERAL}");
+        let (expression, _) = Expression::parse_complete(PositionedBuffer::new(&source)).unwrap();
+        let mut numeral = Application::try_from(expression).unwrap().argument;
+        DeBruijnConverter::convert(&mut numeral);
+        assert_eq!(NumeralEncoding::Church.decode(&numeral), Some(MAX_NUMERAL));
+
+        let source = format!("f {}", MAX_NUMERAL + 1);
+        let error = Expression::parse(PositionedBuffer::new(&source)).unwrap_err();
+        assert!(error.fatal);
+        assert_eq!(error.to_string(), "number literals are at most 1000");
+        assert_eq!(error.span, 2..6);
+
+        let error = Expression::parse(PositionedBuffer::new("100000")).unwrap_err();
+        assert_eq!(error.span, 0..6);
+    }
+
+    #[test]
+    fn test_spans() {
+        let input = PositionedBuffer::new(" λx y. (f x) y");
+        let (expression, _) = Expression::parse(input).unwrap();
+        assert_eq!(expression.span().range(), Some(1..15));
+        let abstraction = Abstraction::try_from(expression).unwrap();
+        assert_eq!(abstraction.parameter.span.range(), Some(3..4));
+        assert_eq!(abstraction.body.span().range(), Some(5..15));
+        let abstraction = Abstraction::try_from(abstraction.body).unwrap();
+        let application = Application::try_from(abstraction.body).unwrap();
+        assert_eq!(application.span.range(), Some(8..15));
+        assert_eq!(application.applicator.span().range(), Some(9..12));
+        assert_eq!(application.argument.span().range(), Some(14..15));
+        assert_eq!(
+            application.applicator,
+            Expression::from(Application {
+                applicator: Variable::from("f".to_string()).into(),
+                argument: Variable::from("x".to_string()).into(),
+                span: Span::default(),
+            })
+        );
+    }
+
+    #[test]
+    fn test_deeply_nested() {
+        let depth = 64;
+        let source = format!("{}x{}", "(".repeat(depth), ")".repeat(depth));
+        let input = PositionedBuffer::new(&source);
+        let (expression, remaining) = Expression::parse(input).unwrap();
+        assert!(remaining.buffer.is_empty());
+        assert_matches!(expression, Expression::Variable(_));
+    }
+
+    #[test]
+    fn test_parse_complete() {
+        let input = PositionedBuffer::new("λx. y x  ");
+        let (expression, remaining) = Expression::parse_complete(input).unwrap();
+        assert!(remaining.buffer.is_empty());
+        assert_matches!(expression, Expression::Abstraction(_));
+
+        let input = PositionedBuffer::new("λx. y x )");
+        let error = Expression::parse_complete(input).unwrap_err();
+        assert_eq!(error.to_string(), "expected end of input");
+        assert_eq!(error.span, 9..10);
+    }
+
+    #[test]
+    fn test_parse_recovering() {
+        let input = PositionedBuffer::new("f (λ. x) (g y) (h (λx y)) z");
+        let (expression, errors) = Expression::parse_recovering(input);
+        assert_eq!(errors.len(), 2);
+        assert_eq!(errors[0].to_string(), "expected a variable");
+        assert_eq!(errors[0].span, 5..6);
+        assert_eq!(errors[1].to_string(), "expected '.'");
+        assert_eq!(errors[1].span, 25..26);
+        let application = Application::try_from(expression).unwrap();
+        assert_eq!(
+            application.argument,
+            Expression::from(Variable::from("z".to_string()))
+        );
+        assert_eq!(application.applicator.errors(), errors);
+    }
+
+    #[test]
+    fn test_parse_recovering_trailing() {
+        let input = PositionedBuffer::new("λ. x\n)");
+        let (expression, errors) = Expression::parse_recovering(input);
+        assert_matches!(expression, Expression::Error(_));
+        assert_eq!(errors.len(), 2);
+        assert_eq!(errors[1].to_string(), "expected end of input");
+        assert_eq!(errors[1].span, 6..7);
+
+        let input = PositionedBuffer::new("λx. x");
+        let (_, errors) = Expression::parse_recovering(input);
+        assert!(errors.is_empty());
+
+        let input = PositionedBuffer::new("f (λ. x)");
+        let (shifted, _) = Expression::parse_recovering(PositionedBuffer::new("f  (λ. x)"));
+        assert_eq!(Expression::parse_recovering(input).0, shifted);
+    }
+
+    #[test]
+    fn test_trace() {
+        trace::enable();
+        let input = PositionedBuffer::new("f x");
+        Expression::parse(input).unwrap();
+        let roots = trace::take();
+        trace::disable();
+
+        assert_eq!(roots.len(), 1);
+        let expression = &roots[0];
+        assert_eq!(expression.name, "expression");
+        assert_eq!(expression.outcome, Some(TraceOutcome::Success { end: 3 }));
+        let children = expression
+            .children
+            .iter()
+            .map(|child| child.name)
+            .collect::<Vec<_>>();
+        assert_eq!(children, vec!["abstraction", "let", "application"]);
+        assert_matches!(
+            &expression.children[0].outcome,
+            Some(TraceOutcome::Failure { position: 0, error }) if error == "expected an abstraction",
+        );
+    }
+
+    #[test]
+    fn test_error_furthest() {
+        let input = PositionedBuffer::new("λx. (b c");
+        let error = Expression::parse(input).unwrap_err();
+        assert_eq!(error.to_string(), "expected ')'");
+        assert_eq!(error.span, 9..10);
+    }
+
+    #[test]
+    fn test_error_committed() {
+        let input = PositionedBuffer::new("f λx y");
+        let error = Expression::parse(input).unwrap_err();
+        assert!(error.fatal);
+        assert_eq!(error.to_string(), "expected '.'");
+        assert_eq!(error.span, 7..8);
+    }
+
+    #[test]
+    fn test_error_expected() {
+        let input = PositionedBuffer::new("λx:. x");
+        let error = Expression::parse(input).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "expected '(', a let expression, a number, a variable or an abstraction"
+        );
+        assert_eq!(error.span, 4..5);
+    }
 }
@@ -1,6 +1,8 @@
+use f_prime_parser::combinators::{sep_by1, spanned};
 use f_prime_parser::{Parser, ParserResult, ThenParserExtensions};
 
 use crate::expression::buffer::{Parsable, PositionedBuffer};
+use crate::expression::source::Span;
 use crate::expression::symbol::{literal_parser, Symbol};
 use crate::expression::variable::Variable;
 use crate::expression::Expression;
@@ -9,10 +11,11 @@ use crate::expression::Expression;
 pub struct Abstraction {
     pub parameter: Variable,
     pub body: Expression,
+    pub span: Span,
 }
 
 impl Abstraction {
-    fn lambda_parser<'a>() -> impl Parser<PositionedBuffer<'a>, Output = Symbol> + 'a {
+    pub fn lambda_parser<'a>() -> impl Parser<PositionedBuffer<'a>, Output = Symbol> + 'a {
         literal_parser("λ")
             .or_else(literal_parser("@"))
             .or_else(literal_parser("\\"))
@@ -23,6 +26,21 @@ impl Abstraction {
             .at_least(1)
             .then_skip(literal_parser("."))
     }
+
+    pub fn tail_parser<'a>() -> impl Parser<PositionedBuffer<'a>, Output = Expression> + 'a {
+        Abstraction::parameters_parser()
+            .then(Expression::parser())
+            .map(|(parameters, body)| {
+                parameters.into_iter().rfold(body, |body, parameter| {
+                    let span = parameter.span.join(&body.span());
+                    Expression::from(Abstraction {
+                        parameter,
+                        body,
+                        span,
+                    })
+                })
+            })
+    }
 }
 
 impl TryFrom<Expression> for Abstraction {
@@ -39,15 +57,9 @@ impl TryFrom<Expression> for Abstraction {
 
 impl Parsable for Abstraction {
     fn parse(input: PositionedBuffer) -> ParserResult<PositionedBuffer, Self> {
-        let parser = Abstraction::lambda_parser()
-            .skip_then(Abstraction::parameters_parser())
-            .then(Expression::parser())
-            .map(|(parameters, body)| {
-                parameters.into_iter().rfold(body, |body, parameter| {
-                    Expression::from(Abstraction { parameter, body })
-                })
-            })
-            .map(|expr| Abstraction::try_from(expr).unwrap());
+        let parser = Abstraction::lambda_parser().skip_then(Abstraction::tail_parser().cut());
+        let parser = spanned(parser)
+            .map(|(expr, span)| Abstraction::try_from(expr.with_span(span)).unwrap());
 
         parser.parse(input)
     }
@@ -58,27 +70,48 @@ pub struct TypedAbstraction {
     pub parameter: Variable,
     pub parameter_type: Expression,
     pub body: Expression,
+    pub span: Span,
 }
 
 impl TypedAbstraction {
     pub fn typed_parameters_parser<'a>(
     ) -> impl Parser<PositionedBuffer<'a>, Output = Vec<(Variable, Expression)>> + 'a {
-        let one = Variable::parser()
+        let parameter = Variable::parser()
             .then_skip(literal_parser(":"))
-            .then(Expression::parser());
+            .then(TypedAbstraction::type_parser());
 
-        let more = literal_parser(",")
-            .skip_then(Variable::parser())
-            .then_skip(literal_parser(":"))
-            .then(Expression::parser())
-            .at_least(0);
+        sep_by1(parameter, literal_parser(",")).then_skip(literal_parser("."))
+    }
 
-        one.then(more)
-            .map(|(first, mut rest)| {
-                rest.insert(0, first);
-                rest
+    /// Parses the type of a parameter, see `PositionedBuffer::in_type`.
+    fn type_parser<'a>() -> impl Parser<PositionedBuffer<'a>, Output = Expression> + 'a {
+        move |input: PositionedBuffer<'a>| {
+            let outer = input.clone();
+            Expression::parser()
+                .parse(input.in_type())
+                .map(|(parameter_type, remaining)| {
+                    (parameter_type, remaining.with_settings_of(&outer))
+                })
+                .map_err(|error| error.map_input(|remaining| remaining.with_settings_of(&outer)))
+        }
+    }
+
+    pub fn tail_parser<'a>() -> impl Parser<PositionedBuffer<'a>, Output = Expression> + 'a {
+        TypedAbstraction::typed_parameters_parser()
+            .then(Expression::parser())
+            .map(|(parameters, body)| {
+                parameters
+                    .into_iter()
+                    .rfold(body, |body, (parameter, parameter_type)| {
+                        let span = parameter.span.join(&body.span());
+                        Expression::from(TypedAbstraction {
+                            parameter,
+                            parameter_type,
+                            body,
+                            span,
+                        })
+                    })
             })
-            .then_skip(literal_parser("."))
     }
 }
 
@@ -96,21 +129,9 @@ impl TryFrom<Expression> for TypedAbstraction {
 
 impl Parsable for TypedAbstraction {
     fn parse(input: PositionedBuffer) -> ParserResult<PositionedBuffer, Self> {
-        let parser = Abstraction::lambda_parser()
-            .skip_then(TypedAbstraction::typed_parameters_parser())
-            .then(Expression::parser())
-            .map(|(parameters, body)| {
-                parameters
-                    .into_iter()
-                    .rfold(body, |body, (parameter, parameter_type)| {
-                        Expression::from(TypedAbstraction {
-                            parameter,
-                            parameter_type,
-                            body,
-                        })
-                    })
-            })
-            .map(|expr| TypedAbstraction::try_from(expr).unwrap());
+        let parser = Abstraction::lambda_parser().skip_then(TypedAbstraction::tail_parser().cut());
+        let parser = spanned(parser)
+            .map(|(expr, span)| TypedAbstraction::try_from(expr.with_span(span)).unwrap());
 
         parser.parse(input)
     }
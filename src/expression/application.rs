@@ -1,28 +1,29 @@
+use f_prime_parser::combinators::spanned;
 use f_prime_parser::{Parser, ParserResult};
 
 use crate::expression::buffer::{Parsable, PositionedBuffer};
+use crate::expression::source::Span;
 use crate::expression::Expression;
 
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub struct Application {
     pub applicator: Expression,
     pub argument: Expression,
+    pub span: Span,
 }
 
 impl Parsable for Application {
     fn parse(input: PositionedBuffer) -> ParserResult<PositionedBuffer, Self> {
-        let parser = Expression::atom_parser().at_least(2).map(|expressions| {
-            expressions
-                .into_iter()
-                .reduce(|applicator, argument| {
-                    Expression::from(Application {
-                        applicator,
-                        argument,
-                    })
-                })
-                .map(|expr| Application::try_from(expr).unwrap())
-                .unwrap()
-        });
+        let applicator = Application::parser()
+            .map(Expression::from)
+            .or_else(Expression::atom_parser());
+        let parser = spanned(applicator.then(Expression::atom_parser()))
+            .map(|((applicator, argument), span)| Application {
+                applicator,
+                argument,
+                span: Span::new(span),
+            })
+            .left_recursive("application");
 
         parser.parse(input)
     }
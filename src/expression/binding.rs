@@ -0,0 +1,142 @@
+use f_prime_parser::combinators::{sep_by1, spanned};
+use f_prime_parser::{Parser, ParserResult, ThenParserExtensions};
+
+use crate::expression::buffer::{Parsable, PositionedBuffer};
+use crate::expression::source::Span;
+use crate::expression::symbol::{keyword_parser, literal_parser};
+use crate::expression::variable::Variable;
+use crate::expression::Expression;
+
+/// A local definition `let variable = value in body`, binding the variable in the body only,
+/// or a recursive one `letrec variable = value in body`, binding it in the value as well.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Let {
+    pub variable: Variable,
+    pub value: Expression,
+    pub body: Expression,
+    pub recursive: bool,
+    /// Set only by `Let::fixpoint`, so that a `letrec` written in the source is never taken
+    /// for a fixed point, whatever its shape.
+    pub fixpoint: bool,
+    pub span: Span,
+}
+
+impl Let {
+    /// The recursive definition `letrec variable = value in variable`, standing for the fixed
+    /// point of `λvariable. value`. It is a value, which is only unfolded when applied.
+    pub fn fixpoint(variable: Variable, value: Expression) -> Self {
+        let reference = Variable {
+            index: 1,
+            ..variable.clone()
+        };
+        Let {
+            variable,
+            value,
+            body: Expression::from(reference),
+            recursive: true,
+            fixpoint: true,
+            span: Span::default(),
+        }
+    }
+
+    fn bindings_parser<'a>(
+    ) -> impl Parser<PositionedBuffer<'a>, Output = Vec<(Variable, Expression)>> + 'a {
+        let binding = Variable::parser()
+            .then_skip(literal_parser("="))
+            .then(Expression::parser());
+
+        sep_by1(binding, literal_parser(",")).then_skip(keyword_parser("in"))
+    }
+
+    /// Parses the bindings and the body after `let` or `letrec`.
+    /// `let x = a, y = b in e` is the same as `let x = a in let y = b in e`,
+    /// so with `letrec` each value can refer to itself and the ones before it.
+    pub fn tail_parser<'a>(
+        recursive: bool,
+    ) -> impl Parser<PositionedBuffer<'a>, Output = Expression> + 'a {
+        Let::bindings_parser()
+            .then(Expression::parser())
+            .map(move |(bindings, body)| {
+                bindings.into_iter().rfold(body, |body, (variable, value)| {
+                    let span = variable.span.join(&body.span());
+                    Expression::from(Let {
+                        variable,
+                        value,
+                        body,
+                        recursive,
+                        fixpoint: false,
+                        span,
+                    })
+                })
+            })
+    }
+}
+
+impl TryFrom<Expression> for Let {
+    type Error = ();
+
+    fn try_from(value: Expression) -> Result<Self, Self::Error> {
+        if let Expression::Let(binding) = value {
+            Ok(*binding)
+        } else {
+            Err(())
+        }
+    }
+}
+
+impl Parsable for Let {
+    fn parse(input: PositionedBuffer) -> ParserResult<PositionedBuffer, Self> {
+        let parser = keyword_parser("let")
+            .skip_then(Let::tail_parser(false).cut())
+            .or_else(keyword_parser("letrec").skip_then(Let::tail_parser(true).cut()));
+        let parser =
+            spanned(parser).map(|(expr, span)| Let::try_from(expr.with_span(span)).unwrap());
+
+        parser.parse(input)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::assert_matches;
+
+    use super::*;
+
+    #[test]
+    fn test_let() {
+        let input = PositionedBuffer::new("let x = f y, y = x in λz. x y z");
+        let (binding, remaining) = Let::parse(input).unwrap();
+        assert!(remaining.buffer.is_empty());
+        assert_eq!(binding.variable.symbol, "x");
+        assert_matches!(binding.value, Expression::Application(_));
+        assert_eq!(binding.span.range(), Some(0..32));
+        let binding = Let::try_from(binding.body).unwrap();
+        assert_eq!(binding.variable.symbol, "y");
+        assert_eq!(binding.span.range(), Some(13..32));
+        assert_matches!(binding.body, Expression::Abstraction(_));
+    }
+
+    #[test]
+    fn test_letrec() {
+        let input = PositionedBuffer::new("letrec f = λx. f x in f");
+        let (binding, _) = Let::parse(input).unwrap();
+        assert!(binding.recursive);
+        assert!(!binding.fixpoint);
+        assert_eq!(binding.span.range(), Some(0..24));
+
+        let input = PositionedBuffer::new("let f = λx. f x in f");
+        let (binding, _) = Let::parse(input).unwrap();
+        assert!(!binding.recursive);
+    }
+
+    #[test]
+    fn test_let_errors() {
+        let input = PositionedBuffer::new("let x = a b");
+        let error = Let::parse(input).unwrap_err();
+        assert!(error.fatal);
+        assert_eq!(error.to_string(), "expected 'in'");
+
+        let input = PositionedBuffer::new("let in = a in b");
+        assert_matches!(Let::parse(input), Err(error) if error.span == (4..5));
+    }
+}
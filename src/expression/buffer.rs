@@ -1,4 +1,13 @@
-use f_prime_parser::{Parser, ParserError, ParserInput, ParserResult};
+use std::ops::Range;
+use std::rc::Rc;
+
+use f_prime_parser::combinators::eof;
+use f_prime_parser::memo::{MemoInput, MemoTable};
+use f_prime_parser::{Parser, ParserError, ParserInput, ParserResult, ThenParserExtensions};
+
+use crate::expression::constant::DefinedConstants;
+use crate::expression::numeral::NumeralEncoding;
+use crate::expression::stream::{Chunk, Pull};
 
 pub trait Parsable
 where
@@ -12,12 +21,128 @@ where
     {
         Self::parse
     }
+
+    /// Parses the whole input, failing on anything but trailing whitespace.
+    fn parse_complete(input: PositionedBuffer) -> ParserResult<PositionedBuffer, Self> {
+        let (output, remaining) = Self::parse(input)?;
+        let ((), remaining) = end_parser().parse(remaining)?;
+        Ok((output, remaining))
+    }
+}
+
+fn skip_group(input: PositionedBuffer) -> ParserResult<PositionedBuffer, ()> {
+    let mut depth = 0;
+    for (offset, c) in input.buffer.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' if depth == 0 => return Ok(((), input.seek(offset + 1))),
+            ')' => depth -= 1,
+            '\n' => return Ok(((), input.seek(offset))),
+            _ => {}
+        }
+    }
+    let length = input.buffer.len();
+    Ok(((), input.seek(length)))
+}
+
+/// Skips past the parenthesis closing the current group, stopping early at the end of the line.
+pub fn skip_group_parser<'a>() -> impl Parser<PositionedBuffer<'a>, Output = ()> + 'a {
+    skip_group
+}
+
+fn skip_line(input: PositionedBuffer) -> ParserResult<PositionedBuffer, ()> {
+    let length = input.buffer.find('\n').unwrap_or(input.buffer.len());
+    Ok(((), input.seek(length)))
+}
+
+/// Skips to the end of the line.
+pub fn skip_line_parser<'a>() -> impl Parser<PositionedBuffer<'a>, Output = ()> + 'a {
+    skip_line
+}
+
+fn unterminated_comment(input: PositionedBuffer) -> ParserResult<PositionedBuffer, ()> {
+    let input = input.seek_trivia();
+    if input.buffer.starts_with("{-") {
+        return Err(input.error("unterminated block comment"));
+    }
+    Ok(((), input))
+}
+
+pub fn end_parser<'a>() -> impl Parser<PositionedBuffer<'a>, Output = ()> + 'a {
+    unterminated_comment.skip_then(eof())
+}
+
+/// The length of the block comment at the start of `text`, which must start with `{-`,
+/// or `None` if it is not terminated.
+fn block_comment_length(text: &str) -> Option<usize> {
+    let mut depth = 0;
+    let mut chars = text.char_indices();
+    while let Some((offset, _)) = chars.next() {
+        let rest = &text[offset..];
+        if rest.starts_with("{-") {
+            depth += 1;
+            chars.next();
+        } else if rest.starts_with("-}") {
+            depth -= 1;
+            chars.next();
+            if depth == 0 {
+                return Some(offset + 2);
+            }
+        }
+    }
+    None
+}
+
+/// The text of a doc comment, which starts with `|`, given the text inside a comment.
+fn doc_comment(comment: &str) -> Option<&str> {
+    comment.trim_start().strip_prefix('|').map(str::trim)
+}
+
+/// The length of the whitespace and comments at the start of `text`,
+/// pushing the text of any doc comments to `docs`.
+/// Stops before a block comment that is not terminated.
+fn trivia_length(text: &str, docs: &mut Vec<String>) -> usize {
+    let mut length = 0;
+    loop {
+        let rest = &text[length..];
+        if let Some(c) = rest.chars().next().filter(|c| c.is_whitespace()) {
+            length += c.len_utf8();
+        } else if let Some(comment) = rest.strip_prefix("--") {
+            let end = comment.find('\n').unwrap_or(comment.len());
+            docs.extend(doc_comment(&comment[..end]).map(str::to_string));
+            length += 2 + end;
+        } else if rest.starts_with("{-") {
+            let Some(end) = block_comment_length(rest) else {
+                return length;
+            };
+            docs.extend(doc_comment(&rest[2..end - 2]).map(str::to_string));
+            length += end;
+        } else {
+            return length;
+        }
+    }
+}
+
+/// How the inputs derived from one another are parsed.
+#[derive(Clone, Debug)]
+struct Settings<'a> {
+    recovering: bool,
+    numerals: NumeralEncoding,
+    constants: &'static [&'static str],
+    conditionals: bool,
+    projections: bool,
+    memo: Rc<MemoTable>,
+    /// Reads the chunks of a stream past the last one read, see `StreamBuffer`.
+    stream: Option<&'a dyn Pull>,
 }
 
 #[derive(Clone, Debug)]
 pub struct PositionedBuffer<'a> {
     pub buffer: &'a str,
     pub position: usize,
+    /// The chunk of a stream the buffer is the rest of, if the input comes from one.
+    chunk: Option<&'a Chunk>,
+    settings: Rc<Settings<'a>>,
 }
 
 impl<'a> PositionedBuffer<'a> {
@@ -25,31 +150,303 @@ impl<'a> PositionedBuffer<'a> {
         PositionedBuffer {
             buffer: input,
             position: 0,
+            chunk: None,
+            settings: Rc::new(Settings {
+                recovering: false,
+                numerals: NumeralEncoding::default(),
+                constants: &[],
+                conditionals: false,
+                projections: true,
+                memo: Rc::default(),
+                stream: None,
+            }),
+        }
+    }
+
+    /// The input from the start of a chunk of a stream on, going on with the chunks after it.
+    /// Once past the chunks read, `stream` reads more if given, or else the input ends.
+    pub(crate) fn from_chunk(chunk: &'a Chunk, stream: Option<&'a dyn Pull>) -> Self {
+        let input = PositionedBuffer::new(chunk.text());
+        PositionedBuffer {
+            position: chunk.start(),
+            chunk: Some(chunk),
+            settings: Rc::new(Settings {
+                stream,
+                ..(*input.settings).clone()
+            }),
+            ..input
+        }
+    }
+
+    fn with_settings(self, settings: Settings<'a>) -> Self {
+        PositionedBuffer {
+            settings: Rc::new(settings),
+            ..self
+        }
+    }
+
+    /// Makes the parsers recover from syntax errors, see `Expression::parse_recovering`.
+    pub fn with_recovery(self) -> Self {
+        if self.settings.recovering {
+            return self;
+        }
+        let settings = Settings {
+            recovering: true,
+            memo: Rc::default(),
+            ..(*self.settings).clone()
+        };
+        self.with_settings(settings)
+    }
+
+    /// Expands number literals with the given encoding instead of as Church numerals.
+    pub fn with_numerals(self, numerals: NumeralEncoding) -> Self {
+        if self.settings.numerals == numerals {
+            return self;
+        }
+        let settings = Settings {
+            numerals,
+            memo: Rc::default(),
+            ..(*self.settings).clone()
+        };
+        self.with_settings(settings)
+    }
+
+    /// How number literals are expanded, see `with_numerals`.
+    pub fn numerals(&self) -> NumeralEncoding {
+        self.settings.numerals
+    }
+
+    /// Parses the constants of a language, which can no longer be used as variables.
+    pub fn with_constants<T: DefinedConstants>(self) -> Self {
+        let settings = Settings {
+            constants: T::CHOICES,
+            memo: Rc::default(),
+            ..(*self.settings).clone()
+        };
+        self.with_settings(settings)
+    }
+
+    /// The constants parsed, see `with_constants`.
+    pub fn constants(&self) -> &'static [&'static str] {
+        self.settings.constants
+    }
+
+    /// Parses `if t then t else t` conditionals, whose keywords can no longer be used as variables.
+    pub fn with_conditionals(self) -> Self {
+        let settings = Settings {
+            conditionals: true,
+            memo: Rc::default(),
+            ..(*self.settings).clone()
+        };
+        self.with_settings(settings)
+    }
+
+    /// Whether conditionals are parsed, see `with_conditionals`.
+    pub fn conditionals(&self) -> bool {
+        self.settings.conditionals
+    }
+
+    /// Parses a type, where there are no projections, so that the dot of `λx:T.1` ends the type.
+    pub fn in_type(self) -> Self {
+        if !self.settings.projections {
+            return self;
+        }
+        let settings = Settings {
+            projections: false,
+            memo: Rc::default(),
+            ..(*self.settings).clone()
+        };
+        self.with_settings(settings)
+    }
+
+    /// Whether projections `.index` are parsed, see `in_type`.
+    pub fn projections(&self) -> bool {
+        self.settings.projections
+    }
+
+    /// Goes on parsing with the settings of `outer`, such as after a type.
+    pub fn with_settings_of(self, outer: &PositionedBuffer<'a>) -> Self {
+        PositionedBuffer {
+            settings: outer.settings.clone(),
+            ..self
         }
     }
 
-    pub fn seek(self, length: usize) -> Self {
+    /// Shares memoized results with other parses of the same source, see `Document`.
+    pub fn with_memo_table(self, memo: Rc<MemoTable>) -> Self {
+        let settings = Settings {
+            memo,
+            ..(*self.settings).clone()
+        };
+        self.with_settings(settings)
+    }
+
+    /// The chunk after the one of the buffer, reading it from the stream if it was not yet.
+    fn next_chunk(&self) -> Option<&'a Chunk> {
+        let chunk = self.chunk?;
+        chunk.next().or_else(|| self.settings.stream?.pull(chunk))
+    }
+
+    /// Moves forward by `length` bytes, which must end on a character boundary.
+    /// Reaching the end of a chunk of a stream moves on to the next one, so that the buffer
+    /// is only empty at the end of the input.
+    pub fn seek(mut self, length: usize) -> Self {
+        let mut offset = length;
+        while offset >= self.buffer.len() {
+            let Some(next) = self.next_chunk() else {
+                break;
+            };
+            offset -= self.buffer.len();
+            self.buffer = next.text();
+            self.chunk = Some(next);
+        }
         PositionedBuffer {
-            buffer: &self.buffer[length..],
+            buffer: &self.buffer[offset..],
             position: self.position + length,
+            ..self
+        }
+    }
+
+    /// Seeks past the text at the start of the buffer that `length` measures,
+    /// going on in the next chunks of a stream.
+    fn seek_while(mut self, mut length: impl FnMut(&str) -> usize) -> Self {
+        loop {
+            match length(self.buffer) {
+                0 => return self,
+                length => self = self.seek(length),
+            }
         }
     }
 
     pub fn seek_whitespace(self) -> Self {
-        let mut ws = 0;
-        for c in self.buffer.chars() {
-            if !c.is_whitespace() {
-                break;
+        self.seek_while(|buffer| {
+            buffer
+                .find(|c: char| !c.is_whitespace())
+                .unwrap_or(buffer.len())
+        })
+    }
+
+    /// Skips whitespace, line comments `-- ...` and nestable block comments `{- ... -}`.
+    pub fn seek_trivia(self) -> Self {
+        self.seek_while(|buffer| trivia_length(buffer, &mut Vec::new()))
+    }
+
+    /// Skips trivia like `seek_trivia`, returning the lines of the doc comments skipped,
+    /// which are the comments starting with `|`, as in `-- | ...` or `{- | ... -}`.
+    pub fn seek_doc_comment(self) -> (Option<String>, Self) {
+        let mut docs = Vec::new();
+        let input = self.seek_while(|buffer| trivia_length(buffer, &mut docs));
+        let doc = (!docs.is_empty()).then(|| docs.join("\n"));
+        (doc, input)
+    }
+
+    /// Whether the input ends inside a block comment.
+    pub fn has_unterminated_comment(&self) -> bool {
+        let mut input = self.clone().seek_trivia();
+        while let Some(c) = input.buffer.chars().next() {
+            if input.buffer.starts_with("{-") {
+                return true;
             }
-            ws += 1;
+            input = input.seek(c.len_utf8()).seek_trivia();
         }
-        self.seek(ws)
+        false
     }
 }
 
 impl<'a> ParserInput for PositionedBuffer<'a> {
-    fn error(self, message: String) -> ParserError<Self> {
-        let range = self.position..self.position;
-        (message, self, range)
+    fn position(&self) -> usize {
+        self.position
+    }
+
+    fn is_empty(&self) -> bool {
+        self.buffer.is_empty()
+    }
+
+    fn span(&self) -> Range<usize> {
+        let length = self.buffer.chars().next().map_or(1, char::len_utf8);
+        self.position..self.position + length
+    }
+
+    fn expected(self, item: impl Into<String>) -> ParserError<Self> {
+        self.settings.memo.examine(self.position + 1);
+        let span = self.span();
+        ParserError::new(self, span).with_expected(item)
+    }
+
+    fn error(self, message: impl Into<String>) -> ParserError<Self> {
+        self.settings.memo.examine(self.position + 1);
+        let span = self.span();
+        ParserError::new(self, span).with_message(message)
+    }
+
+    fn skip_trivia(self) -> Self {
+        self.seek_trivia()
+    }
+
+    fn is_recovering(&self) -> bool {
+        self.settings.recovering
+    }
+}
+
+impl<'a> MemoInput for PositionedBuffer<'a> {
+    fn memo_table(&self) -> &MemoTable {
+        &self.settings.memo
+    }
+
+    fn advance_to(self, position: usize) -> Self {
+        let length = position - self.position;
+        self.seek(length)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::assert_matches;
+
+    use super::*;
+    use crate::expression::Expression;
+
+    #[test]
+    fn test_seek_trivia() {
+        let input = PositionedBuffer::new("  -- comment\n {- block {- nested -} -}\tx -- end");
+        let input = input.seek_trivia();
+        assert_eq!(input.buffer, "x -- end");
+        assert!(input.seek(1).seek_trivia().is_empty());
+
+        let input = PositionedBuffer::new(" {- open {- nested -} x");
+        assert_eq!(input.clone().seek_trivia().position, 1);
+        assert!(input.has_unterminated_comment());
+        assert!(!PositionedBuffer::new("x {- -} -- {-").has_unterminated_comment());
+    }
+
+    #[test]
+    fn test_seek_doc_comment() {
+        let input =
+            PositionedBuffer::new("-- | Identity.\n-- plain\n{- | Returns\n  its argument. -}\nid");
+        let (doc, remaining) = input.seek_doc_comment();
+        assert_eq!(doc.as_deref(), Some("Identity.\nReturns\n  its argument."));
+        assert_eq!(remaining.buffer, "id");
+
+        let (doc, _) = PositionedBuffer::new("-- plain\nid").seek_doc_comment();
+        assert_eq!(doc, None);
+    }
+
+    #[test]
+    fn test_comments_in_expression() {
+        let input =
+            PositionedBuffer::new("λx. -- the body\n  {- applied -} x {- to itself -} x -- done");
+        let (expression, _) = Expression::parse_complete(input).unwrap();
+        assert_eq!(
+            expression,
+            Expression::parse_complete(PositionedBuffer::new("λx. x x"))
+                .unwrap()
+                .0
+        );
+
+        let input = PositionedBuffer::new("x {- never closed");
+        assert_matches!(
+            Expression::parse_complete(input),
+            Err(error) if error.to_string() == "unterminated block comment" && error.span == (2..3)
+        );
     }
 }
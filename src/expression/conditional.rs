@@ -0,0 +1,107 @@
+use f_prime_parser::combinators::spanned;
+use f_prime_parser::{Parser, ParserResult, ThenParserExtensions};
+
+use crate::expression::buffer::{Parsable, PositionedBuffer};
+use crate::expression::constant::constant_symbol;
+use crate::expression::source::Span;
+use crate::expression::symbol::keyword_parser;
+use crate::expression::Expression;
+
+/// The constants a condition has to reduce to for a branch to be taken.
+pub const TRUE: &str = "true";
+pub const FALSE: &str = "false";
+
+/// `if condition then consequent else alternative`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Conditional {
+    pub condition: Expression,
+    pub consequent: Expression,
+    pub alternative: Expression,
+    pub span: Span,
+}
+
+impl Conditional {
+    /// Parses the rest of a conditional after `if`.
+    pub fn tail_parser<'a>() -> impl Parser<PositionedBuffer<'a>, Output = Expression> + 'a {
+        Expression::parser()
+            .then_skip(keyword_parser("then"))
+            .then(Expression::parser())
+            .then_skip(keyword_parser("else"))
+            .then(Expression::parser())
+            .map(|((condition, consequent), alternative)| {
+                Expression::from(Conditional {
+                    condition,
+                    consequent,
+                    alternative,
+                    span: Span::default(),
+                })
+            })
+    }
+
+    /// The branch the condition selects, if it is `true` or `false`.
+    pub fn branch(&self) -> Option<&Expression> {
+        match constant_symbol(&self.condition)? {
+            TRUE => Some(&self.consequent),
+            FALSE => Some(&self.alternative),
+            _ => None,
+        }
+    }
+}
+
+impl TryFrom<Expression> for Conditional {
+    type Error = ();
+
+    fn try_from(value: Expression) -> Result<Self, Self::Error> {
+        if let Expression::Conditional(conditional) = value {
+            Ok(*conditional)
+        } else {
+            Err(())
+        }
+    }
+}
+
+impl Parsable for Conditional {
+    fn parse(input: PositionedBuffer) -> ParserResult<PositionedBuffer, Self> {
+        let parser = keyword_parser("if").skip_then(Conditional::tail_parser().cut());
+        let parser = spanned(parser)
+            .map(|(expr, span)| Conditional::try_from(expr.with_span(span)).unwrap());
+
+        parser.parse(input)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::assert_matches;
+
+    use super::*;
+
+    #[test]
+    fn test_conditional() {
+        let input =
+            PositionedBuffer::new("if f x then λy. y else if z then a else b").with_conditionals();
+        let (conditional, remaining) = Conditional::parse(input).unwrap();
+        assert!(remaining.buffer.is_empty());
+        assert_eq!(conditional.span.range(), Some(0..42));
+        assert_matches!(conditional.condition, Expression::Application(_));
+        assert_matches!(conditional.consequent, Expression::Abstraction(_));
+        assert_matches!(conditional.alternative, Expression::Conditional(_));
+        assert_eq!(conditional.branch(), None);
+
+        let input = PositionedBuffer::new("if x then y").with_conditionals();
+        let error = Conditional::parse(input).unwrap_err();
+        assert!(error.fatal);
+        assert_eq!(error.to_string(), "expected 'else'");
+    }
+
+    #[test]
+    fn test_conditional_disabled() {
+        let input = PositionedBuffer::new("if x then y else z");
+        let (expression, _) = Expression::parse_complete(input).unwrap();
+        assert_matches!(expression, Expression::Application(_));
+
+        let input = PositionedBuffer::new("λthen. x").with_conditionals();
+        let error = Expression::parse(input).unwrap_err();
+        assert_eq!(error.to_string(), "expected a variable");
+    }
+}
@@ -1,15 +1,20 @@
 use std::marker::PhantomData;
 
-use f_prime_parser::combinators::one_of;
-use f_prime_parser::{Parser, ParserResult};
+use f_prime_parser::combinators::spanned;
+use f_prime_parser::{Parser, ParserInput, ParserResult};
 
 use crate::expression::buffer::Parsable;
 use crate::expression::buffer::PositionedBuffer;
-use crate::expression::symbol::{literal_parser, Symbol};
+use crate::expression::source::Span;
+use crate::expression::symbol::{is_identifier, parse_literal, word_parser, Symbol};
+use crate::expression::Expression;
 
-#[derive(Debug)]
-pub struct Constant<T> {
-    symbol: Symbol,
+/// A constant of the language defined by `T`.
+/// In an `Expression` the language is no longer tracked, and `T` is `()`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Constant<T = ()> {
+    pub symbol: Symbol,
+    pub span: Span,
     constants: PhantomData<T>,
 }
 
@@ -17,35 +22,117 @@ impl<T> From<Symbol> for Constant<T> {
     fn from(value: Symbol) -> Self {
         Constant {
             symbol: value,
+            span: Span::default(),
             constants: PhantomData,
         }
     }
 }
 
+impl<T> From<Constant<T>> for Expression {
+    fn from(value: Constant<T>) -> Self {
+        Expression::Constant(Constant {
+            symbol: value.symbol,
+            span: value.span,
+            constants: PhantomData,
+        })
+    }
+}
+
+impl TryFrom<Expression> for Constant {
+    type Error = ();
+
+    fn try_from(value: Expression) -> Result<Self, Self::Error> {
+        if let Expression::Constant(constant) = value {
+            Ok(constant)
+        } else {
+            Err(())
+        }
+    }
+}
+
+/// The expression for the constant `symbol`.
+pub fn constant(symbol: &str) -> Expression {
+    Expression::from(Constant::<()>::from(symbol.to_string()))
+}
+
+/// The symbol of the expression, if it is a constant.
+pub fn constant_symbol(expression: &Expression) -> Option<&str> {
+    match expression {
+        Expression::Constant(constant) => Some(constant.symbol.as_str()),
+        _ => None,
+    }
+}
+
+/// Whether the expression is the constant `symbol`.
+pub fn is_constant(expression: &Expression, symbol: &str) -> bool {
+    constant_symbol(expression) == Some(symbol)
+}
+
 pub trait DefinedConstants {
     const CHOICES: &'static [&'static str];
 }
 
+/// Parses one of `choices`, expecting all of them on failure.
+/// Identifier constants match whole words, so `fix` does not match the start of `fixed`,
+/// while symbolic constants such as `+` match literally, the longest one winning.
+pub fn parse_constant<'a>(
+    choices: &[&str],
+    input: PositionedBuffer<'a>,
+) -> ParserResult<PositionedBuffer<'a>, Constant> {
+    let constant = |((symbol, span), remaining)| {
+        let constant = Constant {
+            span: Span::new(span),
+            ..Constant::from(symbol)
+        };
+        Ok((constant, remaining))
+    };
+    match spanned(word_parser()).parse(input.clone()) {
+        Ok(((symbol, span), remaining)) if choices.contains(&symbol.as_str()) => {
+            return constant(((symbol, span), remaining));
+        }
+        _ => {}
+    }
+
+    let literal = choices
+        .iter()
+        .filter(|choice| !is_identifier(choice))
+        .filter_map(|choice| {
+            spanned(move |input| parse_literal(choice, input))
+                .parse(input.clone())
+                .ok()
+        })
+        .max_by_key(|((symbol, _), _): &((Symbol, _), _)| symbol.len());
+    match literal {
+        Some(parsed) => constant(parsed),
+        None => {
+            let mut error = input.seek_trivia().expected("a constant");
+            error.expected = choices
+                .iter()
+                .map(|constant| format!("'{constant}'"))
+                .collect();
+            Err(error)
+        }
+    }
+}
+
 impl<CONSTANTS> Parsable for Constant<CONSTANTS>
 where
     CONSTANTS: DefinedConstants,
 {
     fn parse(input: PositionedBuffer) -> ParserResult<PositionedBuffer, Self> {
-        let parser = one_of(
-            CONSTANTS::CHOICES
-                .iter()
-                .map(|constant| literal_parser(constant).boxed())
-                .collect(),
-        )
-        .map(Constant::from);
-
-        parser.parse(input)
+        let (constant, remaining) = parse_constant(CONSTANTS::CHOICES, input)?;
+        let constant = Constant {
+            symbol: constant.symbol,
+            span: constant.span,
+            constants: PhantomData,
+        };
+        Ok((constant, remaining))
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use std::assert_matches::assert_matches;
+    use std::assert_matches;
 
     use super::*;
 
@@ -72,5 +159,50 @@ mod tests {
 
         let input = PositionedBuffer::new("else");
         assert_matches!(Constant::<TestConstants>::parse(input), Err(_),);
+
+        let input = PositionedBuffer::new(" fixed");
+        assert_matches!(
+            Constant::<TestConstants>::parse(input),
+            Err(error) if error.to_string() == "expected 'fix' or 'top'" && error.span == (1..2),
+        );
+    }
+
+    #[test]
+    fn test_symbolic_constant() {
+        #[derive(Debug)]
+        struct TestConstants;
+
+        impl DefinedConstants for TestConstants {
+            const CHOICES: &'static [&'static str] = &["+", "++", "0", "fix"];
+        }
+
+        let input = PositionedBuffer::new(" + x");
+        assert_matches!(
+            Constant::<TestConstants>::parse(input),
+            Ok((constant, remaining)) if constant.symbol == "+"
+                && constant.span.range() == Some(1..2)
+                && remaining.buffer == " x",
+        );
+
+        let input = PositionedBuffer::new("++x");
+        assert_matches!(
+            Constant::<TestConstants>::parse(input),
+            Ok((constant, remaining)) if constant.symbol == "++" && remaining.buffer == "x",
+        );
+
+        let input = PositionedBuffer::new("0");
+        assert_matches!(
+            Constant::<TestConstants>::parse(input),
+            Ok((constant, _)) if constant.symbol == "0",
+        );
+
+        let input = PositionedBuffer::new("fix");
+        assert_matches!(
+            Constant::<TestConstants>::parse(input),
+            Ok((constant, _)) if constant.symbol == "fix",
+        );
+
+        let input = PositionedBuffer::new("-");
+        assert_matches!(Constant::<TestConstants>::parse(input), Err(_));
     }
 }
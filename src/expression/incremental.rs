@@ -0,0 +1,242 @@
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::ops::Range;
+use std::rc::Rc;
+
+use f_prime_parser::memo::MemoTable;
+
+use crate::expression::buffer::PositionedBuffer;
+use crate::expression::{Expression, SyntaxError};
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Edit {
+    pub range: Range<usize>,
+    pub text: String,
+}
+
+impl Edit {
+    pub fn new(range: Range<usize>, text: impl Into<String>) -> Self {
+        Edit {
+            range,
+            text: text.into(),
+        }
+    }
+}
+
+/// An edit whose range does not fit the source it is applied to.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum EditError {
+    /// The range ends past the end of the source, or before it starts.
+    OutOfBounds(Range<usize>),
+    /// The range starts or ends inside a character.
+    NotCharBoundary(Range<usize>),
+}
+
+impl Display for EditError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EditError::OutOfBounds(range) => write!(f, "edit range {range:?} is out of bounds"),
+            EditError::NotCharBoundary(range) => {
+                write!(f, "edit range {range:?} is not on character boundaries")
+            }
+        }
+    }
+}
+
+impl Error for EditError {}
+
+/// A source kept parsed across edits.
+/// Reparsing after an edit reuses the memoized parses of the regions the edit did not touch.
+pub struct Document {
+    source: String,
+    memo: Rc<MemoTable>,
+    expression: Expression,
+    errors: Vec<SyntaxError>,
+}
+
+impl Document {
+    pub fn new(source: impl Into<String>) -> Self {
+        let source = source.into();
+        let memo = Rc::default();
+        let (expression, errors) = Document::parse(&source, &memo);
+        Document {
+            source,
+            memo,
+            expression,
+            errors,
+        }
+    }
+
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    pub fn expression(&self) -> &Expression {
+        &self.expression
+    }
+
+    pub fn errors(&self) -> &[SyntaxError] {
+        &self.errors
+    }
+
+    /// Applies an edit and reparses the source, returning the updated expression along with
+    /// the ranges of its nodes that differ from the previous expression.
+    /// Fails without changing the document if the range of the edit does not fit the source.
+    pub fn edit(&mut self, edit: Edit) -> Result<(&Expression, Vec<Range<usize>>), EditError> {
+        let range = edit.range.clone();
+        if range.start > range.end || range.end > self.source.len() {
+            return Err(EditError::OutOfBounds(range));
+        }
+        if !self.source.is_char_boundary(range.start) || !self.source.is_char_boundary(range.end) {
+            return Err(EditError::NotCharBoundary(range));
+        }
+        self.source.replace_range(edit.range.clone(), &edit.text);
+        self.memo.edit(edit.range, edit.text.len());
+
+        let (expression, errors) = Document::parse(&self.source, &self.memo);
+        let previous = std::mem::replace(&mut self.expression, expression);
+        self.errors = errors;
+        let mut changed = Vec::new();
+        changed_nodes(Some(&previous), &self.expression, &mut changed);
+        changed.sort_by_key(|range| (range.start, range.end));
+
+        Ok((&self.expression, changed))
+    }
+
+    fn parse(source: &str, memo: &Rc<MemoTable>) -> (Expression, Vec<SyntaxError>) {
+        let input = PositionedBuffer::new(source)
+            .with_recovery()
+            .with_memo_table(memo.clone());
+        Expression::parse_recovering(input)
+    }
+}
+
+/// The subexpressions of an expression, in source order.
+fn children(expression: &Expression) -> Vec<&Expression> {
+    match expression {
+        Expression::Variable(_) | Expression::Constant(_) | Expression::Error(_) => vec![],
+        Expression::Abstraction(abstraction) => vec![&abstraction.body],
+        Expression::TypedAbstraction(abstraction) => {
+            vec![&abstraction.parameter_type, &abstraction.body]
+        }
+        Expression::Application(application) => {
+            vec![&application.applicator, &application.argument]
+        }
+        Expression::Let(binding) => vec![&binding.value, &binding.body],
+        Expression::Conditional(conditional) => vec![
+            &conditional.condition,
+            &conditional.consequent,
+            &conditional.alternative,
+        ],
+        Expression::Tuple(tuple) => tuple.elements.iter().collect(),
+        Expression::Projection(projection) => vec![&projection.tuple],
+    }
+}
+
+/// Collects the ranges of the nodes of `new` that differ from their counterpart in `old`.
+/// Nodes of the same kind with as many children are compared child by child, and the other
+/// nodes are new along with all their descendants.
+fn changed_nodes(old: Option<&Expression>, new: &Expression, changed: &mut Vec<Range<usize>>) {
+    if old == Some(new) {
+        return;
+    }
+    changed.extend(new.span().range());
+    let new_children = children(new);
+    let old_children = match old {
+        Some(old) if std::mem::discriminant(old) == std::mem::discriminant(new) => children(old),
+        _ => vec![],
+    };
+    if old_children.len() == new_children.len() {
+        for (old, new) in old_children.into_iter().zip(new_children) {
+            changed_nodes(Some(old), new, changed);
+        }
+    } else {
+        for new in new_children {
+            changed_nodes(None, new, changed);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::expression::application::Application;
+
+    fn parse(source: &str) -> Expression {
+        Expression::parse_recovering(PositionedBuffer::new(source)).0
+    }
+
+    #[test]
+    fn test_edit() {
+        let mut document = Document::new("f (g x) (h y)");
+        let (expression, changed) = document.edit(Edit::new(5..6, "z")).unwrap();
+        assert_eq!(*expression, parse("f (g z) (h y)"));
+        assert_eq!(changed, vec![0..7, 0..13, 3..6, 5..6]);
+
+        let (_, changed) = document.edit(Edit::new(1..1, " ")).unwrap();
+        assert!(changed.is_empty());
+    }
+
+    #[test]
+    fn test_edit_shifted() {
+        let mut document = Document::new("f (g x) (h y)");
+        let (expression, changed) = document.edit(Edit::new(5..6, "(λv. v)")).unwrap();
+        assert_eq!(*expression, parse("f (g (λv. v)) (h y)"));
+        assert_eq!(changed, vec![0..14, 0..20, 3..13, 6..12, 11..12]);
+        let application = Application::try_from(expression.clone()).unwrap();
+        assert_eq!(application.argument.span().range(), Some(16..19));
+        assert_eq!(document.source(), "f (g (λv. v)) (h y)");
+    }
+
+    #[test]
+    fn test_edit_sequence() {
+        let mut document = Document::new("x");
+        let edits = [
+            (1..1, " y"),
+            (3..3, " "),
+            (4..4, "(λa"),
+            (8..8, "."),
+            (9..9, " a z)"),
+            (0..1, "f g"),
+            (5..6, ""),
+        ];
+        for (range, text) in edits {
+            document.edit(Edit::new(range, text)).unwrap();
+            assert_eq!(*document.expression(), parse(document.source()));
+        }
+        assert_eq!(document.source(), "f g y(λa. a z)");
+        assert!(document.errors().is_empty());
+    }
+
+    #[test]
+    fn test_edit_errors() {
+        let mut document = Document::new("f (λx. x)");
+        document.edit(Edit::new(5..6, "")).unwrap();
+        assert_eq!(document.errors().len(), 1);
+        document.edit(Edit::new(5..5, "y")).unwrap();
+        assert!(document.errors().is_empty());
+    }
+
+    #[test]
+    fn test_edit_invalid_range() {
+        let mut document = Document::new("f (λx. x)");
+        assert_eq!(
+            document.edit(Edit::new(8..12, "")).unwrap_err(),
+            EditError::OutOfBounds(8..12)
+        );
+        assert_eq!(
+            document.edit(Edit::new(4..5, "y")).unwrap_err(),
+            EditError::NotCharBoundary(4..5)
+        );
+        assert_eq!(document.source(), "f (λx. x)");
+        assert!(document.errors().is_empty());
+    }
+
+    #[test]
+    fn test_edit_relocates_errors() {
+        let mut document = Document::new("(y z:: :\n (,( .");
+        document.edit(Edit::new(4..6, "")).unwrap();
+        let (_, errors) = Expression::parse_recovering(PositionedBuffer::new(document.source()));
+        assert_eq!(document.errors(), errors);
+    }
+}
@@ -0,0 +1,366 @@
+use std::fmt::{Display, Formatter};
+use std::ops::Range;
+use std::rc::Rc;
+
+use f_prime_parser::combinators::{many_till, one_of};
+use f_prime_parser::memo::{MemoInput, MemoTable};
+use f_prime_parser::{Parser, ParserError, ParserInput, ParserResult, ThenParserExtensions};
+
+use crate::expression::abstraction::Abstraction;
+use crate::expression::application::Application;
+use crate::expression::buffer::{end_parser, PositionedBuffer};
+use crate::expression::numeral::{NumeralEncoding, MAX_NUMERAL};
+use crate::expression::source::Span;
+use crate::expression::symbol::{literal_parser, number_parser, word_parser, Symbol, KEYWORDS};
+use crate::expression::variable::Variable;
+use crate::expression::Expression;
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum TokenKind {
+    Lambda,
+    Dot,
+    Colon,
+    Comma,
+    LeftParenthesis,
+    RightParenthesis,
+    Identifier(Symbol),
+    Number(u64),
+    Keyword(Symbol),
+}
+
+impl Display for TokenKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TokenKind::Lambda => write!(f, "'λ'"),
+            TokenKind::Dot => write!(f, "'.'"),
+            TokenKind::Colon => write!(f, "':'"),
+            TokenKind::Comma => write!(f, "','"),
+            TokenKind::LeftParenthesis => write!(f, "'('"),
+            TokenKind::RightParenthesis => write!(f, "')'"),
+            TokenKind::Identifier(symbol) | TokenKind::Keyword(symbol) => write!(f, "'{symbol}'"),
+            TokenKind::Number(number) => write!(f, "'{number}'"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Token {
+    pub kind: TokenKind,
+    pub span: Range<usize>,
+}
+
+fn token_kind_parser<'a>() -> impl Parser<PositionedBuffer<'a>, Output = TokenKind> + 'a {
+    one_of(vec![
+        Abstraction::lambda_parser()
+            .map(|_| TokenKind::Lambda)
+            .boxed(),
+        literal_parser(".").map(|_| TokenKind::Dot).boxed(),
+        literal_parser(":").map(|_| TokenKind::Colon).boxed(),
+        literal_parser(",").map(|_| TokenKind::Comma).boxed(),
+        literal_parser("(")
+            .map(|_| TokenKind::LeftParenthesis)
+            .boxed(),
+        literal_parser(")")
+            .map(|_| TokenKind::RightParenthesis)
+            .boxed(),
+        number_parser().map(TokenKind::Number).boxed(),
+        word_parser()
+            .map(|symbol| {
+                if KEYWORDS.contains(&symbol.as_str()) {
+                    TokenKind::Keyword(symbol)
+                } else {
+                    TokenKind::Identifier(symbol)
+                }
+            })
+            .boxed(),
+    ])
+}
+
+fn parse_token(input: PositionedBuffer) -> ParserResult<PositionedBuffer, Token> {
+    let input = input.seek_trivia();
+    let start = input.position;
+    let (kind, remaining) = token_kind_parser().parse(input)?;
+    let span = start..remaining.position;
+    Ok((Token { kind, span }, remaining))
+}
+
+pub fn tokenize(input: PositionedBuffer) -> ParserResult<PositionedBuffer, Vec<Token>> {
+    many_till(parse_token, end_parser()).parse(input)
+}
+
+#[derive(Clone, Debug)]
+pub struct TokenBuffer<'a> {
+    pub tokens: &'a [Token],
+    pub position: usize,
+    end: usize,
+    /// The end of the last token consumed in the source.
+    consumed: usize,
+    memo: Rc<MemoTable>,
+}
+
+impl<'a> TokenBuffer<'a> {
+    pub fn new(tokens: &'a [Token]) -> Self {
+        TokenBuffer {
+            tokens,
+            position: 0,
+            end: tokens.last().map_or(0, |token| token.span.end),
+            consumed: tokens.first().map_or(0, |token| token.span.start),
+            memo: Rc::default(),
+        }
+    }
+
+    pub fn seek(self, length: usize) -> Self {
+        let consumed = match length {
+            0 => self.consumed,
+            length => self.tokens[length - 1].span.end,
+        };
+        TokenBuffer {
+            tokens: &self.tokens[length..],
+            position: self.position + length,
+            end: self.end,
+            consumed,
+            memo: self.memo,
+        }
+    }
+}
+
+impl<'a> ParserInput for TokenBuffer<'a> {
+    fn position(&self) -> usize {
+        self.position
+    }
+
+    fn is_empty(&self) -> bool {
+        self.tokens.is_empty()
+    }
+
+    fn span(&self) -> Range<usize> {
+        self.tokens
+            .first()
+            .map_or(self.end..self.end + 1, |token| token.span.clone())
+    }
+}
+
+impl<'a> MemoInput for TokenBuffer<'a> {
+    fn memo_table(&self) -> &MemoTable {
+        &self.memo
+    }
+
+    fn advance_to(self, position: usize) -> Self {
+        let length = position - self.position;
+        self.seek(length)
+    }
+}
+
+fn parse_token_if<'a, O>(
+    input: TokenBuffer<'a>,
+    expected: impl Display,
+    f: impl Fn(&Token) -> Option<O>,
+) -> ParserResult<TokenBuffer<'a>, O> {
+    match input.tokens.first().and_then(f) {
+        Some(output) => Ok((output, input.seek(1))),
+        None => Err(input.expected(expected.to_string())),
+    }
+}
+
+pub fn token_parser<'a>(kind: TokenKind) -> impl Parser<TokenBuffer<'a>, Output = Token> + 'a {
+    move |input: TokenBuffer<'a>| {
+        parse_token_if(input, &kind, |token| {
+            (token.kind == kind).then(|| token.clone())
+        })
+    }
+}
+
+pub fn identifier_token_parser<'a>() -> impl Parser<TokenBuffer<'a>, Output = Symbol> + 'a {
+    |input: TokenBuffer<'a>| {
+        parse_token_if(input, "a symbol", |token| match &token.kind {
+            TokenKind::Identifier(symbol) => Some(symbol.clone()),
+            _ => None,
+        })
+    }
+}
+
+pub fn number_token_parser<'a>() -> impl Parser<TokenBuffer<'a>, Output = u64> + 'a {
+    |input: TokenBuffer<'a>| {
+        parse_token_if(input, "a number", |token| match &token.kind {
+            TokenKind::Number(number) => Some(*number),
+            _ => None,
+        })
+    }
+}
+
+/// Like `spanned`, with the range of the source covered by the tokens parsed.
+fn spanned_tokens<'a, O>(
+    p: impl Parser<TokenBuffer<'a>, Output = O> + 'a,
+) -> impl Parser<TokenBuffer<'a>, Output = (O, Span)> + 'a {
+    move |input: TokenBuffer<'a>| {
+        let start = input.span().start;
+        let (output, remaining) = p.parse(input)?;
+        let span = Span::new(start..remaining.consumed.max(start));
+        Ok(((output, span), remaining))
+    }
+}
+
+fn variable_token_parser<'a>() -> impl Parser<TokenBuffer<'a>, Output = Variable> + 'a {
+    |input: TokenBuffer<'a>| {
+        parse_token_if(input, "a variable", |token| match &token.kind {
+            TokenKind::Identifier(symbol) => Some(Variable {
+                span: Span::new(token.span.clone()),
+                ..Variable::from(symbol.clone())
+            }),
+            _ => None,
+        })
+    }
+}
+
+fn parse_abstraction_tokens(input: TokenBuffer) -> ParserResult<TokenBuffer, Expression> {
+    let tail = variable_token_parser()
+        .at_least(1)
+        .then_skip(token_parser(TokenKind::Dot))
+        .then(parse_expression_tokens);
+    let parser = token_parser(TokenKind::Lambda).skip_then(tail.cut());
+    let parser = spanned_tokens(parser).map(|((parameters, body), span)| {
+        let abstraction = parameters.into_iter().rfold(body, |body, parameter| {
+            let span = parameter.span.join(&body.span());
+            Expression::from(Abstraction {
+                parameter,
+                body,
+                span,
+            })
+        });
+        abstraction.with_span(span)
+    });
+    parser.parse(input)
+}
+
+fn parse_numeral_tokens(input: TokenBuffer) -> ParserResult<TokenBuffer, Expression> {
+    let span = input.span();
+    let (number, remaining) = number_token_parser().parse(input.clone())?;
+    if number > MAX_NUMERAL {
+        let error = ParserError::new(input, span)
+            .with_message(format!("number literals are at most {MAX_NUMERAL}"))
+            .with_fatal();
+        return Err(error);
+    }
+    let numeral = NumeralEncoding::default().encode(number);
+    Ok((numeral.with_span(span), remaining))
+}
+
+fn parse_atom_tokens(input: TokenBuffer) -> ParserResult<TokenBuffer, Expression> {
+    let group = token_parser(TokenKind::LeftParenthesis).skip_then(
+        parse_expression_tokens
+            .then_skip(token_parser(TokenKind::RightParenthesis))
+            .cut(),
+    );
+    parse_abstraction_tokens
+        .or_else(parse_numeral_tokens)
+        .or_else(variable_token_parser().map(Expression::from))
+        .or_else(group)
+        .parse(input)
+}
+
+fn parse_expression_tokens(input: TokenBuffer) -> ParserResult<TokenBuffer, Expression> {
+    let start = input.span().start;
+    let (atoms, remaining) = parse_atom_tokens.at_least(1).parse(input)?;
+    let application = atoms
+        .into_iter()
+        .reduce(|applicator, argument| {
+            let span = Span::new(start..argument.span().range().map_or(start, |span| span.end));
+            Expression::from(Application {
+                applicator,
+                argument,
+                span,
+            })
+        })
+        .unwrap();
+    Ok((application, remaining))
+}
+
+impl Expression {
+    /// Parses the untyped lambda calculus, abstractions, applications, variables and numbers,
+    /// from the tokens of `tokenize`. Spans refer to the source the tokens were read from.
+    pub fn parse_tokens(input: TokenBuffer) -> ParserResult<TokenBuffer, Expression> {
+        parse_expression_tokens(input)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::assert_matches;
+
+    use super::*;
+    use crate::expression::buffer::Parsable;
+
+    fn kinds(tokens: &[Token]) -> Vec<TokenKind> {
+        tokens.iter().map(|token| token.kind.clone()).collect()
+    }
+
+    #[test]
+    fn test_tokenize() {
+        let input = PositionedBuffer::new("λx:T, y. let f = x 42");
+        assert_matches!(tokenize(input), Err(error) if error.span == (16..17));
+
+        let input = PositionedBuffer::new("λx:T, y. let z 42 ");
+        let (tokens, remaining) = tokenize(input).unwrap();
+        assert!(remaining.buffer.is_empty());
+        assert_eq!(
+            kinds(&tokens),
+            vec![
+                TokenKind::Lambda,
+                TokenKind::Identifier("x".to_string()),
+                TokenKind::Colon,
+                TokenKind::Identifier("T".to_string()),
+                TokenKind::Comma,
+                TokenKind::Identifier("y".to_string()),
+                TokenKind::Dot,
+                TokenKind::Keyword("let".to_string()),
+                TokenKind::Identifier("z".to_string()),
+                TokenKind::Number(42),
+            ]
+        );
+        assert_eq!(tokens[0].span, 0..2);
+        assert_eq!(tokens[7].span, 10..13);
+        assert_eq!(tokens[9].span, 16..18);
+    }
+
+    #[test]
+    fn test_token_buffer() {
+        let input = PositionedBuffer::new("λx y. x");
+        let (tokens, _) = tokenize(input).unwrap();
+        let parser = token_parser(TokenKind::Lambda)
+            .skip_then(identifier_token_parser().at_least(1))
+            .then_skip(token_parser(TokenKind::Dot))
+            .then(identifier_token_parser());
+
+        let input = TokenBuffer::new(&tokens);
+        let ((parameters, body), remaining) = parser.parse(input).unwrap();
+        assert_eq!(parameters, vec!["x", "y"]);
+        assert_eq!(body, "x");
+        assert!(remaining.is_empty());
+
+        let input = TokenBuffer::new(&tokens[..3]);
+        let error = parser.parse(input).unwrap_err();
+        assert_eq!(error.to_string(), "expected '.'");
+        assert_eq!(error.span, 5..6);
+    }
+
+    #[test]
+    fn test_parse_tokens() {
+        let source = "(λx y. x y) z 2";
+        let (tokens, _) = tokenize(PositionedBuffer::new(source)).unwrap();
+        let (expression, remaining) = Expression::parse_tokens(TokenBuffer::new(&tokens)).unwrap();
+        assert!(remaining.is_empty());
+        let (parsed, _) = Expression::parse_complete(PositionedBuffer::new(source)).unwrap();
+        assert_eq!(expression, parsed);
+        assert_eq!(expression.span().range(), parsed.span().range());
+        assert_eq!(expression.span().range(), Some(0..16));
+        let application = Application::try_from(expression).unwrap();
+        let application = Application::try_from(application.applicator).unwrap();
+        assert_eq!(application.span.range(), Some(0..14));
+        assert_eq!(application.applicator.span().range(), Some(1..11));
+
+        let (tokens, _) = tokenize(PositionedBuffer::new("f (λx. )")).unwrap();
+        let error = Expression::parse_tokens(TokenBuffer::new(&tokens)).unwrap_err();
+        assert!(error.fatal);
+        assert_eq!(error.span, 8..9);
+    }
+}
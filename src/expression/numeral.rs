@@ -0,0 +1,261 @@
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+use crate::expression::abstraction::Abstraction;
+use crate::expression::application::Application;
+use crate::expression::constant::{constant, is_constant};
+use crate::expression::source::Span;
+use crate::expression::variable::{DeBruijnIndex, Variable};
+use crate::expression::Expression;
+
+/// The constants unary numerals are made of.
+pub const ZERO: &str = "0";
+pub const SUCCESSOR: &str = "succ";
+
+/// The largest number literal. Most encodings nest as deep as the number they encode,
+/// and the traversals of expressions recurse into every level.
+pub const MAX_NUMERAL: u64 = 1000;
+
+/// How number literals are expanded into terms, and read back from them.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum NumeralEncoding {
+    /// `n` applies its first argument `n` times to its second, `2 = λs. λz. s (s z)`.
+    #[default]
+    Church,
+    /// `n` passes its predecessor to its second argument, `0 = λz. λs. z` and `n = λz. λs. s (n - 1)`.
+    Scott,
+    /// `n` is the list of its bits starting from the least significant one, where
+    /// `nil = λn. λc. n`, `cons b l = λn. λc. c b l`, `0 = λt. λf. f` and `1 = λt. λf. t`.
+    Binary,
+    /// `n` is the constant `succ` applied `n` times to the constant `0`, as in TAPL's arithmetic.
+    Unary,
+}
+
+fn variable(symbol: &str) -> Expression {
+    Expression::from(Variable::from(symbol.to_string()))
+}
+
+fn abstraction(parameter: &str, body: Expression) -> Expression {
+    Expression::from(Abstraction {
+        parameter: Variable::from(parameter.to_string()),
+        body,
+        span: Span::default(),
+    })
+}
+
+fn application(applicator: Expression, argument: Expression) -> Expression {
+    Expression::from(Application {
+        applicator,
+        argument,
+        span: Span::default(),
+    })
+}
+
+/// The body of `λ. λ. body`.
+fn inner_body(expression: &Expression) -> Option<&Expression> {
+    let Expression::Abstraction(outer) = expression else {
+        return None;
+    };
+    let Expression::Abstraction(inner) = &outer.body else {
+        return None;
+    };
+    Some(&inner.body)
+}
+
+fn index(expression: &Expression) -> Option<DeBruijnIndex> {
+    match expression {
+        Expression::Variable(variable) => Some(variable.index),
+        _ => None,
+    }
+}
+
+/// The applicator and the argument of an application.
+fn parts(expression: &Expression) -> Option<(&Expression, &Expression)> {
+    match expression {
+        Expression::Application(application) => {
+            Some((&application.applicator, &application.argument))
+        }
+        _ => None,
+    }
+}
+
+impl NumeralEncoding {
+    /// The term for `number`, which still has to be converted by `DeBruijnConverter`.
+    pub fn encode(&self, number: u64) -> Expression {
+        match self {
+            NumeralEncoding::Church => {
+                let body =
+                    (0..number).fold(variable("z"), |body, _| application(variable("s"), body));
+                abstraction("s", abstraction("z", body))
+            }
+            NumeralEncoding::Scott => (0..number).fold(
+                abstraction("z", abstraction("s", variable("z"))),
+                |predecessor, _| {
+                    abstraction(
+                        "z",
+                        abstraction("s", application(variable("s"), predecessor)),
+                    )
+                },
+            ),
+            NumeralEncoding::Binary => {
+                let bits = u64::BITS - number.leading_zeros();
+                (0..bits).rev().fold(
+                    abstraction("n", abstraction("c", variable("n"))),
+                    |rest, bit| {
+                        let bit = if number >> bit & 1 == 1 { "t" } else { "f" };
+                        let bit = abstraction("t", abstraction("f", variable(bit)));
+                        let cons = application(application(variable("c"), bit), rest);
+                        abstraction("n", abstraction("c", cons))
+                    },
+                )
+            }
+            NumeralEncoding::Unary => (0..number).fold(constant(ZERO), |predecessor, _| {
+                application(constant(SUCCESSOR), predecessor)
+            }),
+        }
+    }
+
+    /// The number a term converted by `DeBruijnConverter` encodes, if it is shaped like a numeral.
+    pub fn decode(&self, expression: &Expression) -> Option<u64> {
+        match self {
+            NumeralEncoding::Church => {
+                let mut body = inner_body(expression)?;
+                let mut number = 0u64;
+                while let Some((applicator, argument)) = parts(body) {
+                    if index(applicator)? != 2 {
+                        return None;
+                    }
+                    number = number.checked_add(1)?;
+                    body = argument;
+                }
+                (index(body)? == 1).then_some(number)
+            }
+            NumeralEncoding::Scott => {
+                let mut numeral = expression;
+                let mut number = 0u64;
+                loop {
+                    let body = inner_body(numeral)?;
+                    if index(body) == Some(2) {
+                        return Some(number);
+                    }
+                    let (applicator, predecessor) = parts(body)?;
+                    if index(applicator)? != 1 {
+                        return None;
+                    }
+                    number = number.checked_add(1)?;
+                    numeral = predecessor;
+                }
+            }
+            NumeralEncoding::Binary => {
+                let mut list = expression;
+                let mut number = 0u64;
+                for bit in 0..=u64::BITS {
+                    let body = inner_body(list)?;
+                    if index(body) == Some(2) {
+                        return Some(number);
+                    }
+                    let (cons, rest) = parts(body)?;
+                    let (head, value) = parts(cons)?;
+                    if index(head)? != 1 {
+                        return None;
+                    }
+                    if index(inner_body(value)?)? == 2 {
+                        number |= 1u64.checked_shl(bit)?;
+                    } else if index(inner_body(value)?)? != 1 {
+                        return None;
+                    }
+                    list = rest;
+                }
+                None
+            }
+            NumeralEncoding::Unary => {
+                let mut numeral = expression;
+                let mut number = 0u64;
+                while let Some((successor, predecessor)) = parts(numeral) {
+                    if !is_constant(successor, SUCCESSOR) {
+                        return None;
+                    }
+                    number = number.checked_add(1)?;
+                    numeral = predecessor;
+                }
+                is_constant(numeral, ZERO).then_some(number)
+            }
+        }
+    }
+}
+
+impl Display for NumeralEncoding {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            NumeralEncoding::Church => write!(f, "church"),
+            NumeralEncoding::Scott => write!(f, "scott"),
+            NumeralEncoding::Binary => write!(f, "binary"),
+            NumeralEncoding::Unary => write!(f, "unary"),
+        }
+    }
+}
+
+impl FromStr for NumeralEncoding {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "church" => Ok(NumeralEncoding::Church),
+            "scott" => Ok(NumeralEncoding::Scott),
+            "binary" => Ok(NumeralEncoding::Binary),
+            "unary" => Ok(NumeralEncoding::Unary),
+            _ => Err(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::traverse::de_bruijn::convert::DeBruijnConverter;
+    use crate::traverse::pretty_print::ExpressionPrettyPrinter;
+
+    const ENCODINGS: [NumeralEncoding; 4] = [
+        NumeralEncoding::Church,
+        NumeralEncoding::Scott,
+        NumeralEncoding::Binary,
+        NumeralEncoding::Unary,
+    ];
+
+    #[test]
+    fn test_encode() {
+        let encode = |encoding: NumeralEncoding, number| {
+            let mut expression = encoding.encode(number);
+            DeBruijnConverter::convert(&mut expression);
+            ExpressionPrettyPrinter::format_indexed(&expression)
+        };
+        assert_eq!(encode(NumeralEncoding::Church, 3), "λ λ 2 (2 (2 1))");
+        assert_eq!(encode(NumeralEncoding::Scott, 0), "λ λ 2");
+        assert_eq!(encode(NumeralEncoding::Scott, 2), "λ λ 1 (λ λ 1 (λ λ 2))");
+        assert_eq!(
+            encode(NumeralEncoding::Binary, 2),
+            "λ λ 1 (λ λ 1) (λ λ 1 (λ λ 2) (λ λ 2))"
+        );
+        assert_eq!(encode(NumeralEncoding::Unary, 2), "succ (succ 0)");
+    }
+
+    #[test]
+    fn test_decode() {
+        for encoding in ENCODINGS {
+            for number in [0, 1, 2, 5, 12] {
+                let mut expression = encoding.encode(number);
+                DeBruijnConverter::convert(&mut expression);
+                assert_eq!(encoding.decode(&expression), Some(number), "{encoding}");
+            }
+        }
+
+        let mut expression = NumeralEncoding::Church.encode(2);
+        DeBruijnConverter::convert(&mut expression);
+        assert_eq!(NumeralEncoding::Scott.decode(&expression), None);
+        let mut identity = abstraction("x", variable("x"));
+        DeBruijnConverter::convert(&mut identity);
+        for encoding in ENCODINGS {
+            assert_eq!(encoding.decode(&identity), None);
+        }
+    }
+}
@@ -0,0 +1,187 @@
+use f_prime_parser::combinators::spanned;
+use f_prime_parser::{Parser, ParserInput, ParserResult, ThenParserExtensions};
+
+use crate::expression::buffer::{end_parser, skip_line_parser, Parsable, PositionedBuffer};
+use crate::expression::source::Span;
+use crate::expression::symbol::literal_parser;
+use crate::expression::variable::Variable;
+use crate::expression::{Expression, SyntaxError};
+use crate::traverse::de_bruijn::shift::DeBruijnShift;
+use crate::traverse::de_bruijn::substitution::DeBruijnSubstitution;
+
+/// A top-level declaration `name = term;`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Definition {
+    pub name: Variable,
+    pub body: Expression,
+    /// The doc comments right before the definition, see `PositionedBuffer::seek_doc_comment`.
+    pub doc: Option<String>,
+    pub span: Span,
+}
+
+impl Parsable for Definition {
+    fn parse(input: PositionedBuffer) -> ParserResult<PositionedBuffer, Self> {
+        let (doc, input) = input.seek_doc_comment();
+        let body = Expression::parser()
+            .then_skip(literal_parser(";"))
+            .recover(skip_line_parser(), Expression::from_error);
+        let parser = Variable::parser()
+            .then_skip(literal_parser("="))
+            .then(body.cut());
+        let parser = spanned(parser).map(|((name, body), span)| Definition {
+            name,
+            body,
+            doc: doc.clone(),
+            span: Span::new(span),
+        });
+
+        parser.parse(input)
+    }
+}
+
+/// A sequence of definitions, each of which may refer to the ones before it,
+/// followed by an optional main expression.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Program {
+    pub definitions: Vec<Definition>,
+    pub main: Option<Expression>,
+}
+
+impl Parsable for Program {
+    fn parse(input: PositionedBuffer) -> ParserResult<PositionedBuffer, Self> {
+        let (definitions, remaining) = Definition::parser().at_least(0).parse(input)?;
+        if remaining.clone().seek_trivia().is_empty() {
+            let program = Program {
+                definitions,
+                main: None,
+            };
+            return Ok((program, remaining));
+        }
+
+        let main = Expression::parser().recover(skip_line_parser(), Expression::from_error);
+        let (main, remaining) = main.parse(remaining)?;
+        let program = Program {
+            definitions,
+            main: Some(main),
+        };
+        Ok((program, remaining))
+    }
+}
+
+impl Program {
+    /// The last definition of `name`, which shadows any earlier ones.
+    pub fn definition(&self, name: &str) -> Option<&Definition> {
+        self.definitions
+            .iter()
+            .rev()
+            .find(|definition| definition.name.symbol == name)
+    }
+
+    /// The syntax errors of the error nodes in this program, from left to right.
+    pub fn errors(&self) -> Vec<SyntaxError> {
+        self.definitions
+            .iter()
+            .map(|definition| &definition.body)
+            .chain(&self.main)
+            .flat_map(Expression::errors)
+            .collect()
+    }
+
+    /// Parses the whole input like `Expression::parse_recovering`.
+    pub fn parse_recovering(input: PositionedBuffer) -> (Program, Vec<SyntaxError>) {
+        let (program, remaining) = match Program::parse(input.with_recovery()) {
+            Ok(parsed) => parsed,
+            Err(error) => return (Program::default(), vec![error.map_input(|_| ())]),
+        };
+        let mut errors = program.errors();
+        if let Err(error) = end_parser().parse(remaining) {
+            errors.push(error.map_input(|_| ()));
+        }
+        (program, errors)
+    }
+
+    /// The main expression with the definitions it refers to substituted in.
+    /// The program must have been converted with `DeBruijnConverter::convert_program`.
+    pub fn inline(&self) -> Option<Expression> {
+        let mut main = self.main.clone()?;
+        for definition in self.definitions.iter().rev() {
+            let mut body = definition.body.clone();
+            DeBruijnShift::shift(1, &mut body);
+            DeBruijnSubstitution::substitute(1, body, &mut main);
+            DeBruijnShift::shift(-1, &mut main);
+        }
+        Some(main)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::traverse::de_bruijn::convert::DeBruijnConverter;
+    use crate::traverse::pretty_print::ExpressionPrettyPrinter;
+
+    const SOURCE: &str = "\
+-- | The identity.
+id = λx. x;
+-- | Applies a function twice.
+twice = λf x. f (f x);
+twice id y
+";
+
+    #[test]
+    fn test_program() {
+        let (program, errors) = Program::parse_recovering(PositionedBuffer::new(SOURCE));
+        assert!(errors.is_empty());
+        assert_eq!(program.definitions.len(), 2);
+        let twice = program.definition("twice").unwrap();
+        assert_eq!(twice.doc.as_deref(), Some("Applies a function twice."));
+        assert_eq!(twice.span.range(), Some(63..86));
+        assert_eq!(
+            ExpressionPrettyPrinter::format_named(program.main.as_ref().unwrap()),
+            "twice id y"
+        );
+
+        let input = PositionedBuffer::new("id = λx. x;\n");
+        let (program, _) = Program::parse_complete(input).unwrap();
+        assert_eq!(program.main, None);
+    }
+
+    #[test]
+    fn test_program_errors() {
+        let input = PositionedBuffer::new("id = λx. x;\nf = ;\nid");
+        let (program, errors) = Program::parse_recovering(input);
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].span, 17..18);
+        assert_eq!(program.definitions.len(), 2);
+        assert!(program.main.is_some());
+
+        let input = PositionedBuffer::new("id = λx. x");
+        let (_, errors) = Program::parse_recovering(input);
+        assert_eq!(errors[0].to_string(), "expected ';'");
+        assert_eq!(errors[0].span, 11..12);
+    }
+
+    #[test]
+    fn test_inline() {
+        let (mut program, _) = Program::parse_recovering(PositionedBuffer::new(SOURCE));
+        DeBruijnConverter::convert_program(&mut program);
+        let main = program.inline().unwrap();
+        assert_eq!(
+            ExpressionPrettyPrinter::format_named(&main),
+            "(λf. λx. f (f x)) (λx. x) y"
+        );
+        assert_eq!(
+            ExpressionPrettyPrinter::format_indexed(&main),
+            "(λ λ 2 (2 1)) (λ 1) 1"
+        );
+
+        let input = PositionedBuffer::new("f = λx. x y z;\nf z y");
+        let (mut program, _) = Program::parse_recovering(input);
+        DeBruijnConverter::convert_program(&mut program);
+        let main = program.inline().unwrap();
+        assert_eq!(
+            ExpressionPrettyPrinter::format_indexed(&main),
+            "(λ 1 2 3) 2 1"
+        );
+    }
+}
@@ -0,0 +1,253 @@
+use std::fmt::{Display, Formatter};
+use std::ops::Range;
+
+use crate::expression::buffer::PositionedBuffer;
+
+/// A line and column in a source, both starting at 1. Columns count characters, not bytes.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct Location {
+    pub line: usize,
+    pub column: usize,
+}
+
+impl Location {
+    pub fn start() -> Self {
+        Location { line: 1, column: 1 }
+    }
+
+    /// The location right after `text`, when `text` starts at this location.
+    pub fn advance(self, text: &str) -> Self {
+        match text.rfind('\n') {
+            Some(index) => Location {
+                line: self.line + text.matches('\n').count(),
+                column: text[index + 1..].chars().count() + 1,
+            },
+            None => Location {
+                line: self.line,
+                column: self.column + text.chars().count(),
+            },
+        }
+    }
+}
+
+impl Default for Location {
+    fn default() -> Self {
+        Location::start()
+    }
+}
+
+impl Display for Location {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.line, self.column)
+    }
+}
+
+/// The byte range an expression node was parsed from, if it was parsed at all.
+/// Spans are ignored by equality, so that nodes compare structurally.
+#[derive(Clone, Debug, Default)]
+pub struct Span(pub Option<Range<usize>>);
+
+impl Span {
+    pub fn new(range: Range<usize>) -> Self {
+        Span(Some(range))
+    }
+
+    pub fn range(&self) -> Option<Range<usize>> {
+        self.0.clone()
+    }
+
+    /// The smallest span covering both spans, if both are known.
+    pub fn join(&self, other: &Span) -> Span {
+        match (&self.0, &other.0) {
+            (Some(this), Some(other)) => {
+                Span::new(this.start.min(other.start)..this.end.max(other.end))
+            }
+            _ => Span::default(),
+        }
+    }
+
+    pub fn shift(&mut self, offset: isize) {
+        if let Some(range) = &mut self.0 {
+            let shift = |position: usize| position.saturating_add_signed(offset);
+            *range = shift(range.start)..shift(range.end);
+        }
+    }
+}
+
+impl PartialEq for Span {
+    fn eq(&self, _other: &Self) -> bool {
+        true
+    }
+}
+
+impl Eq for Span {}
+
+impl From<Range<usize>> for Span {
+    fn from(value: Range<usize>) -> Self {
+        Span::new(value)
+    }
+}
+
+/// A named source text, such as a loaded file or the lines entered in the REPL.
+#[derive(Clone, Debug)]
+pub struct Source {
+    pub name: String,
+    text: String,
+    /// The byte offsets at which each line starts.
+    line_starts: Vec<usize>,
+}
+
+impl Source {
+    pub fn new(name: impl Into<String>, text: impl Into<String>) -> Self {
+        let mut source = Source {
+            name: name.into(),
+            text: String::new(),
+            line_starts: vec![0],
+        };
+        source.push_str(&text.into());
+        source
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Appends to the text, e.g. a line just read.
+    pub fn push_str(&mut self, text: &str) {
+        let offset = self.text.len();
+        self.text.push_str(text);
+        self.line_starts.extend(
+            text.match_indices('\n')
+                .map(|(index, _)| offset + index + 1),
+        );
+    }
+
+    /// The location of a byte offset, which is clamped to the text and to character boundaries.
+    pub fn location(&self, offset: usize) -> Location {
+        let offset = self.text.floor_char_boundary(offset);
+        let line = self.line_starts.partition_point(|start| *start <= offset);
+        let line_start = self.line_starts[line - 1];
+        Location {
+            line,
+            column: self.text[line_start..offset].chars().count() + 1,
+        }
+    }
+
+    /// The text of a byte range, widened to character boundaries and clamped to the text.
+    pub fn slice(&self, range: Range<usize>) -> &str {
+        let start = self.text.floor_char_boundary(range.start);
+        let end = self.text.ceil_char_boundary(range.end).max(start);
+        &self.text[start..end]
+    }
+
+    /// The text of a line, without its line break.
+    pub fn line(&self, line: usize) -> Option<&str> {
+        let start = *self.line_starts.get(line.checked_sub(1)?)?;
+        let end = self
+            .line_starts
+            .get(line)
+            .map_or(self.text.len(), |end| end - 1);
+        Some(self.text[start..end].trim_end_matches('\r'))
+    }
+
+    pub fn buffer(&self) -> PositionedBuffer<'_> {
+        PositionedBuffer::new(&self.text)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct SourceId(usize);
+
+/// A location along with the name of its source, displayed as `name:line:column`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct SourceLocation<'a> {
+    pub name: &'a str,
+    pub location: Location,
+}
+
+impl<'a> Display for SourceLocation<'a> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.name, self.location)
+    }
+}
+
+/// The sources parsed so far, so that byte offsets into them can be reported as locations.
+#[derive(Clone, Debug, Default)]
+pub struct SourceMap {
+    sources: Vec<Source>,
+}
+
+impl SourceMap {
+    pub fn new() -> Self {
+        SourceMap::default()
+    }
+
+    pub fn add(&mut self, source: Source) -> SourceId {
+        self.sources.push(source);
+        SourceId(self.sources.len() - 1)
+    }
+
+    pub fn get(&self, id: SourceId) -> &Source {
+        &self.sources[id.0]
+    }
+
+    pub fn get_mut(&mut self, id: SourceId) -> &mut Source {
+        &mut self.sources[id.0]
+    }
+
+    pub fn locate(&self, id: SourceId, offset: usize) -> SourceLocation<'_> {
+        let source = self.get(id);
+        SourceLocation {
+            name: &source.name,
+            location: source.location(offset),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_location() {
+        let source = Source::new("test.lam", "λx.\n  λy. x\r\nz");
+        assert_eq!(source.location(0), Location { line: 1, column: 1 });
+        assert_eq!(source.location(2), Location { line: 1, column: 2 });
+        assert_eq!(source.location(1), Location { line: 1, column: 1 });
+        assert_eq!(source.location(6), Location { line: 2, column: 2 });
+        assert_eq!(source.location(8), Location { line: 2, column: 3 });
+        assert_eq!(source.location(15), Location { line: 3, column: 1 });
+        assert_eq!(source.location(100), Location { line: 3, column: 2 });
+        assert_eq!(source.line(2), Some("  λy. x"));
+        assert_eq!(source.line(3), Some("z"));
+        assert_eq!(source.line(4), None);
+        assert_eq!(Location::start().advance("λx.\n  λy"), source.location(10));
+    }
+
+    #[test]
+    fn test_buffer_location() {
+        let source = Source::new("test.lam", "\u{a0}λx.\n  x");
+        let buffer = source.buffer().seek_whitespace();
+        assert_eq!(buffer.position, 2);
+        assert_eq!(
+            source.location(buffer.position),
+            Location { line: 1, column: 2 }
+        );
+        let buffer = buffer.seek(4).seek_whitespace();
+        assert_eq!(buffer.buffer, "x");
+        assert_eq!(
+            source.location(buffer.position),
+            Location { line: 2, column: 3 }
+        );
+    }
+
+    #[test]
+    fn test_source_map() {
+        let mut sources = SourceMap::new();
+        let repl = sources.add(Source::new("<stdin>", "f x\n"));
+        let file = sources.add(Source::new("file.lam", "x\n\n  (λy. y"));
+        sources.get_mut(repl).push_str("g )\n");
+        assert_eq!(sources.locate(repl, 6).to_string(), "<stdin>:2:3");
+        assert_eq!(sources.locate(file, 11).to_string(), "file.lam:3:8");
+    }
+}
@@ -0,0 +1,471 @@
+use std::cell::{Cell, OnceCell, RefCell};
+use std::fmt::{Debug, Display, Formatter};
+use std::io::{BufRead, BufReader, Read};
+use std::rc::Rc;
+
+use f_prime_parser::memo::MemoTable;
+use f_prime_parser::{Parser, ParserInput, ParserResult, ThenParserExtensions};
+
+use crate::expression::buffer::{end_parser, skip_line_parser, Parsable, PositionedBuffer};
+use crate::expression::numeral::NumeralEncoding;
+use crate::expression::program::Program;
+use crate::expression::symbol::literal_parser;
+use crate::expression::{Expression, SyntaxError};
+
+#[derive(Debug)]
+pub enum StreamError {
+    /// The input read so far is a prefix of an expression, more lines are needed.
+    Incomplete,
+    Syntax(Vec<SyntaxError>),
+    Io(std::io::Error),
+}
+
+impl Display for StreamError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StreamError::Incomplete => write!(f, "incomplete input"),
+            StreamError::Syntax(errors) => match errors.first() {
+                Some(error) => write!(f, "syntax error: {error}"),
+                None => write!(f, "syntax error"),
+            },
+            StreamError::Io(error) => write!(f, "{error}"),
+        }
+    }
+}
+
+impl std::error::Error for StreamError {}
+
+impl From<std::io::Error> for StreamError {
+    fn from(value: std::io::Error) -> Self {
+        StreamError::Io(value)
+    }
+}
+
+/// A line of a stream, along with the lines after it up to the end of any block comment it opens,
+/// so that no token or comment spans two chunks.
+#[derive(Debug)]
+pub struct Chunk {
+    /// Where the chunk starts in the stream.
+    start: usize,
+    text: String,
+    next: OnceCell<Box<Chunk>>,
+}
+
+impl Chunk {
+    fn new(start: usize, text: String) -> Self {
+        Chunk {
+            start,
+            text,
+            next: OnceCell::new(),
+        }
+    }
+
+    pub(crate) fn start(&self) -> usize {
+        self.start
+    }
+
+    pub(crate) fn text(&self) -> &str {
+        &self.text
+    }
+
+    fn end(&self) -> usize {
+        self.start + self.text.len()
+    }
+
+    /// The chunk after this one, if it was read.
+    pub(crate) fn next(&self) -> Option<&Chunk> {
+        self.next.get().map(|next| &**next)
+    }
+
+    fn opens_comment(&self) -> bool {
+        PositionedBuffer::new(&self.text).has_unterminated_comment()
+    }
+}
+
+/// Reads the chunk after the last one read of a stream, for parsers that get to the end of it.
+pub(crate) trait Pull: Debug {
+    /// Reads the chunk after `chunk`, which must be the last one read.
+    /// Returns `None` at the end of the stream.
+    fn pull<'c>(&self, chunk: &'c Chunk) -> Option<&'c Chunk>;
+}
+
+struct StreamReader<R> {
+    reader: RefCell<BufReader<R>>,
+    exhausted: Cell<bool>,
+    /// The error that ended the stream while parsing, which is reported once the parse is over.
+    error: RefCell<Option<std::io::Error>>,
+}
+
+impl<R> Debug for StreamReader<R> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("StreamReader")
+            .field("exhausted", &self.exhausted)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<R: Read> StreamReader<R> {
+    /// Appends the next line to `text`, returning its length in bytes, or 0 at the end.
+    fn read_line(&self, text: &mut String) -> std::io::Result<usize> {
+        let length = self.reader.borrow_mut().read_line(text)?;
+        if length == 0 {
+            self.exhausted.set(true);
+        }
+        Ok(length)
+    }
+}
+
+impl<R: Read> Pull for StreamReader<R> {
+    fn pull<'c>(&self, chunk: &'c Chunk) -> Option<&'c Chunk> {
+        let mut next = Chunk::new(chunk.end(), String::new());
+        loop {
+            match self.read_line(&mut next.text) {
+                Ok(0) => break,
+                Ok(_) if next.opens_comment() => continue,
+                Ok(_) => break,
+                Err(error) => {
+                    self.exhausted.set(true);
+                    self.error.replace(Some(error));
+                    break;
+                }
+            }
+        }
+        if next.text.is_empty() {
+            return None;
+        }
+        chunk.next.set(Box::new(next)).ok()?;
+        chunk.next()
+    }
+}
+
+/// The output of an entry of a stream along with its syntax errors, and where it ends.
+type Entry<T> = (Option<T>, Vec<SyntaxError>, usize);
+
+/// The `;` or the end of the input ending an entry.
+fn terminator_parser<'a>() -> impl Parser<PositionedBuffer<'a>, Output = ()> + 'a {
+    literal_parser(";").map(|_| ()).or_else(end_parser())
+}
+
+/// Ends an entry where it was parsed up to, or else at the end of the line where it failed.
+fn finish_entry<T>(
+    result: ParserResult<PositionedBuffer, T>,
+    errors: impl FnOnce(&T) -> Vec<SyntaxError>,
+) -> Entry<T> {
+    match result {
+        Ok((output, remaining)) => {
+            let errors = errors(&output);
+            (Some(output), errors, remaining.position())
+        }
+        Err(error) => {
+            let end = match skip_line_parser().parse(error.input.clone()) {
+                Ok((_, remaining)) => remaining.position(),
+                Err(_) => error.input.position(),
+            };
+            (None, vec![error.map_input(|_| ())], end)
+        }
+    }
+}
+
+fn parse_expression(input: PositionedBuffer) -> Entry<Expression> {
+    let parser = Expression::parser()
+        .recover(skip_line_parser(), Expression::from_error)
+        .then_skip(terminator_parser());
+    finish_entry(parser.parse(input), Expression::errors)
+}
+
+fn parse_program(input: PositionedBuffer) -> Entry<Program> {
+    let parser = |input| {
+        let (program, remaining) = Program::parse(input)?;
+        if program.main.is_none() {
+            return Ok((program, remaining));
+        }
+        let ((), remaining) = terminator_parser().parse(remaining)?;
+        Ok((program, remaining))
+    };
+    finish_entry(parser.parse(input), Program::errors)
+}
+
+/// Expressions read from any `io::Read`, each ending with `;` or at the end of the input.
+/// Lines are read as the parsers get to the end of the ones read before, and are dropped
+/// once the entries on them are parsed.
+pub struct StreamBuffer<R> {
+    reader: StreamReader<R>,
+    /// The first chunk that is not consumed yet, or else the last one read.
+    head: Box<Chunk>,
+    /// Where the pending input starts in the stream.
+    start: usize,
+    memo: Rc<MemoTable>,
+    numerals: NumeralEncoding,
+}
+
+impl<R: Read> StreamBuffer<R> {
+    pub fn new(reader: R) -> Self {
+        StreamBuffer {
+            reader: StreamReader {
+                reader: RefCell::new(BufReader::new(reader)),
+                exhausted: Cell::new(false),
+                error: RefCell::new(None),
+            },
+            head: Box::new(Chunk::new(0, String::new())),
+            start: 0,
+            memo: Rc::default(),
+            numerals: NumeralEncoding::default(),
+        }
+    }
+
+    fn chunks(&self) -> impl Iterator<Item = &Chunk> {
+        std::iter::successors(Some(&*self.head), |chunk| chunk.next())
+    }
+
+    /// The end of the input read so far.
+    fn end(&self) -> usize {
+        self.chunks().last().map_or(0, Chunk::end)
+    }
+
+    /// The input read from `position` on, which must not be before the last entry parsed.
+    /// Positions in syntax errors are byte offsets into the whole input.
+    pub fn read_since(&self, position: usize) -> String {
+        assert!(
+            position >= self.head.start,
+            "input before {} was dropped",
+            self.head.start
+        );
+        self.chunks()
+            .filter(|chunk| chunk.end() > position)
+            .map(|chunk| &chunk.text[position.saturating_sub(chunk.start)..])
+            .collect()
+    }
+
+    /// The input read but not yet parsed into an expression.
+    pub fn pending(&self) -> String {
+        self.read_since(self.start)
+    }
+
+    /// Whether the end of the underlying reader was reached.
+    pub fn is_exhausted(&self) -> bool {
+        self.reader.exhausted.get()
+    }
+
+    /// Expands the number literals parsed from now on with the given encoding.
+    pub fn set_numerals(&mut self, numerals: NumeralEncoding) {
+        self.numerals = numerals;
+        self.memo.clear();
+    }
+
+    /// Discards the pending input.
+    pub fn clear(&mut self) {
+        self.consume(self.end());
+    }
+
+    fn consume(&mut self, end: usize) {
+        self.start = end;
+        self.memo.clear();
+    }
+
+    /// Drops the chunks before the pending input.
+    fn drop_consumed(&mut self) {
+        while self.head.end() <= self.start {
+            match self.head.next.take() {
+                Some(next) => self.head = next,
+                None => break,
+            }
+        }
+    }
+
+    /// Pulls the next line into the pending input, returning its length in bytes.
+    /// Returns 0 once the reader is exhausted.
+    pub fn read_line(&mut self) -> std::io::Result<usize> {
+        let mut text = String::new();
+        let length = self.reader.read_line(&mut text)?;
+        let end = self.end();
+        let mut tail = &mut self.head;
+        while tail.next.get().is_some() {
+            tail = tail.next.get_mut().unwrap();
+        }
+        if tail.opens_comment() {
+            tail.text.push_str(&text);
+        } else if length > 0 {
+            let _ = tail.next.set(Box::new(Chunk::new(end, text)));
+        }
+        self.memo.edit(end..end, length);
+        Ok(length)
+    }
+
+    fn input<'s>(&'s self, stream: Option<&'s dyn Pull>) -> PositionedBuffer<'s> {
+        PositionedBuffer::from_chunk(&self.head, stream)
+            .seek(self.start - self.head.start)
+            .with_recovery()
+            .with_numerals(self.numerals)
+            .with_memo_table(self.memo.clone())
+    }
+
+    /// Parses the next entry of the pending input, without reading any more, taking it on success
+    /// and up to the end of the line of the error on a syntax error.
+    /// Returns `Ok(None)` if there is nothing but whitespace and comments pending.
+    /// Input that ends before the entry does is only a syntax error once the reader is exhausted.
+    pub fn try_parse(&mut self) -> Result<Option<Expression>, StreamError> {
+        self.try_parse_with(parse_expression)
+    }
+
+    /// Parses the pending input like `try_parse`, as definitions followed by an optional expression.
+    pub fn try_parse_program(&mut self) -> Result<Option<Program>, StreamError> {
+        self.try_parse_with(parse_program)
+    }
+
+    fn try_parse_with<T>(
+        &mut self,
+        parse: impl FnOnce(PositionedBuffer) -> Entry<T>,
+    ) -> Result<Option<T>, StreamError> {
+        self.drop_consumed();
+        let end = self.end();
+        let exhausted = self.is_exhausted();
+        let input = self.input(None);
+        if input.has_unterminated_comment() && !exhausted {
+            return Err(StreamError::Incomplete);
+        }
+        if input.clone().seek_trivia().is_empty() {
+            drop(input);
+            self.clear();
+            return Ok(None);
+        }
+
+        let (output, errors, entry_end) = parse(input);
+        let incomplete = errors.iter().all(|error| error.span.start >= end);
+        if !errors.is_empty() && incomplete && !exhausted {
+            return Err(StreamError::Incomplete);
+        }
+        self.consume(entry_end);
+        match output {
+            Some(output) if errors.is_empty() => Ok(Some(output)),
+            _ => Err(StreamError::Syntax(errors)),
+        }
+    }
+
+    /// Parses the next entry, reading as much as it takes.
+    /// Returns `Ok(None)` at the end of the input.
+    pub fn next_expression(&mut self) -> Result<Option<Expression>, StreamError> {
+        self.next_with(parse_expression)
+    }
+
+    /// Parses the next entry as definitions followed by an optional expression,
+    /// reading as much as it takes. Returns `Ok(None)` at the end of the input.
+    pub fn next_program(&mut self) -> Result<Option<Program>, StreamError> {
+        self.next_with(parse_program)
+    }
+
+    fn next_with<T>(
+        &mut self,
+        parse: impl FnOnce(PositionedBuffer) -> Entry<T>,
+    ) -> Result<Option<T>, StreamError> {
+        self.drop_consumed();
+        let (output, errors, end) = {
+            let input = self.input(Some(&self.reader));
+            let trivia = input.clone().seek_trivia();
+            if trivia.is_empty() {
+                (None, vec![], trivia.position())
+            } else {
+                parse(input)
+            }
+        };
+        self.consume(end);
+        if let Some(error) = self.reader.error.take() {
+            return Err(StreamError::Io(error));
+        }
+        match output {
+            Some(output) if errors.is_empty() => Ok(Some(output)),
+            None if errors.is_empty() => Ok(None),
+            _ => Err(StreamError::Syntax(errors)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::assert_matches;
+
+    use super::*;
+
+    fn parse(source: &str) -> Expression {
+        Expression::parse_recovering(PositionedBuffer::new(source)).0
+    }
+
+    #[test]
+    fn test_next_expression() {
+        let mut stream = StreamBuffer::new("f x;\n\n(λy.\n  y\n  z) w;\nv\n  u".as_bytes());
+        assert_eq!(stream.next_expression().unwrap(), Some(parse("f x")));
+        assert_eq!(stream.read_since(0), "f x;\n");
+        assert_eq!(
+            stream.next_expression().unwrap(),
+            Some(parse("(λy. y z) w"))
+        );
+        assert_eq!(stream.next_expression().unwrap(), Some(parse("v u")));
+        assert_eq!(stream.next_expression().unwrap(), None);
+        assert!(stream.is_exhausted());
+    }
+
+    #[test]
+    fn test_drop_consumed() {
+        let mut stream = StreamBuffer::new("a;\nb;\nc;\n".as_bytes());
+        stream.next_expression().unwrap();
+        stream.next_expression().unwrap();
+        assert_eq!(stream.head.start, 0);
+        assert_eq!(stream.next_expression().unwrap(), Some(parse("c")));
+        assert_eq!(stream.head.start, 3);
+        assert_eq!(stream.read_since(6), "c;\n");
+        assert_eq!(stream.next_expression().unwrap(), None);
+        assert_eq!(stream.head.start, 6);
+    }
+
+    #[test]
+    fn test_try_parse() {
+        let mut stream = StreamBuffer::new("(λx.\nx\n".as_bytes());
+        assert_eq!(stream.try_parse().unwrap(), None);
+        stream.read_line().unwrap();
+        assert_matches!(stream.try_parse(), Err(StreamError::Incomplete));
+        assert_eq!(stream.pending(), "(λx.\n");
+        stream.read_line().unwrap();
+        assert_matches!(stream.try_parse(), Err(StreamError::Incomplete));
+        stream.read_line().unwrap();
+        assert_matches!(stream.try_parse(), Err(StreamError::Syntax(errors)) if errors.len() == 1);
+        assert!(stream.pending().is_empty());
+
+        let mut stream = StreamBuffer::new("f x; g\n{- open\n-} y\n".as_bytes());
+        stream.read_line().unwrap();
+        assert_eq!(stream.try_parse().unwrap(), Some(parse("f x")));
+        assert_eq!(stream.try_parse().unwrap(), Some(parse("g")));
+        stream.read_line().unwrap();
+        assert_matches!(stream.try_parse(), Err(StreamError::Incomplete));
+        stream.read_line().unwrap();
+        assert_eq!(stream.try_parse().unwrap(), Some(parse("y")));
+    }
+
+    #[test]
+    fn test_syntax_error() {
+        let mut stream = StreamBuffer::new("f ) (λx.\ny\nz )".as_bytes());
+        assert_matches!(stream.next_expression(), Err(StreamError::Syntax(errors)) if errors[0].span == (2..3));
+        assert_matches!(stream.next_expression(), Err(StreamError::Syntax(errors)) if errors[0].span == (14..15));
+        assert_eq!(stream.next_expression().unwrap(), None);
+    }
+
+    #[test]
+    fn test_comments() {
+        let mut stream =
+            StreamBuffer::new("-- comment\nf {- multi\nline -} x;\n{- dangling".as_bytes());
+        assert_eq!(stream.next_expression().unwrap(), Some(parse("f x")));
+        assert_matches!(stream.next_expression(), Err(StreamError::Syntax(_)));
+        assert_eq!(stream.next_expression().unwrap(), None);
+    }
+
+    #[test]
+    fn test_next_program() {
+        let mut stream =
+            StreamBuffer::new("id = λx.\n  x;\nf = id; g = f;\nf y;\nid\n  z\n".as_bytes());
+        let program = stream.next_program().unwrap().unwrap();
+        assert_eq!(program.definitions.len(), 3);
+        assert_eq!(program.main, Some(parse("f y")));
+        let program = stream.next_program().unwrap().unwrap();
+        assert!(program.definitions.is_empty());
+        assert_eq!(program.main, Some(parse("id z")));
+        assert_eq!(stream.next_program().unwrap(), None);
+    }
+}
@@ -1,48 +1,126 @@
 use f_prime_parser::{Parser, ParserInput, ParserResult};
+use unicode_xid::UnicodeXID;
 
 use crate::expression::buffer::PositionedBuffer;
 
 pub type Symbol = String;
 
-fn parse_symbol(input: PositionedBuffer) -> ParserResult<PositionedBuffer, Symbol> {
-    let input = input.seek_whitespace();
-    let mut chars = input.buffer.chars();
+pub const KEYWORDS: &[&str] = &["let", "letrec", "in"];
+
+/// Also reserved when conditionals are parsed, see `PositionedBuffer::with_conditionals`.
+pub const CONDITIONAL_KEYWORDS: &[&str] = &["if", "then", "else"];
+
+/// Reserved for abstractions, even though it is a letter.
+const LAMBDA: char = 'λ';
+
+fn is_identifier_start(c: char) -> bool {
+    c != LAMBDA && (c == '_' || c.is_xid_start())
+}
+
+fn is_subscript(c: char) -> bool {
+    ('\u{2080}'..='\u{209c}').contains(&c)
+}
 
-    let mut matched = 0;
+fn is_identifier_continue(c: char) -> bool {
+    c != LAMBDA && (c.is_xid_continue() || is_subscript(c))
+}
+
+/// The length of the identifier at the start of `text`, which may be 0.
+/// Identifiers start with a Unicode XID start character or `_`, continue with XID continue
+/// characters, subscripts and single dashes, and may end with primes `'` and question marks.
+fn identifier_length(text: &str) -> usize {
+    let mut chars = text.char_indices().peekable();
     match chars.next() {
-        Some(c) if c.is_ascii_alphabetic() => matched += 1,
-        _ => {
-            return Err(input.error("Invalid symbol.".to_string()));
-        }
+        Some((_, c)) if is_identifier_start(c) => {}
+        _ => return 0,
     }
 
-    for c in chars {
-        if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
-            matched += 1;
-        } else {
+    let mut length = text.len();
+    while let Some((offset, c)) = chars.next() {
+        let next = chars.peek().map(|(_, next)| *next);
+        let dash = c == '-' && next.is_some_and(|next| next != '-' && is_identifier_continue(next));
+        if !(is_identifier_continue(c) || dash) {
+            length = offset;
             break;
         }
     }
+    let suffix = text[length..]
+        .find(|c: char| c != '\'' && c != '?')
+        .unwrap_or(text.len() - length);
+    length + suffix
+}
+
+/// Whether all of `text` is a single identifier.
+pub fn is_identifier(text: &str) -> bool {
+    !text.is_empty() && identifier_length(text) == text.len()
+}
+
+fn parse_word(input: PositionedBuffer) -> ParserResult<PositionedBuffer, Symbol> {
+    let input = input.seek_trivia();
+    match identifier_length(input.buffer) {
+        0 => Err(input.expected("a symbol")),
+        length => Ok((input.buffer[..length].to_string(), input.seek(length))),
+    }
+}
 
-    Ok((input.buffer[0..matched].to_string(), input.seek(matched)))
+/// Parses an identifier or a keyword.
+pub fn word_parser<'a>() -> impl Parser<PositionedBuffer<'a>, Output = Symbol> + 'a {
+    parse_word
+}
+
+fn parse_symbol(input: PositionedBuffer) -> ParserResult<PositionedBuffer, Symbol> {
+    let (symbol, remaining) = parse_word(input.clone())?;
+    let conditional = input.conditionals() && CONDITIONAL_KEYWORDS.contains(&symbol.as_str());
+    if conditional || KEYWORDS.contains(&symbol.as_str()) {
+        return Err(input.seek_trivia().expected("a symbol"));
+    }
+    Ok((symbol, remaining))
 }
 
+/// Parses an identifier that is not a keyword.
 pub fn symbol_parser<'a>() -> impl Parser<PositionedBuffer<'a>, Output = Symbol> + 'a {
     parse_symbol
 }
 
+/// Parses the given keyword as a whole word, so that `let` does not match the start of `letter`.
+pub fn keyword_parser<'a>(
+    keyword: &'static str,
+) -> impl Parser<PositionedBuffer<'a>, Output = Symbol> + 'a {
+    move |input: PositionedBuffer<'a>| match parse_word(input.clone()) {
+        Ok((word, remaining)) if word == keyword => Ok((word, remaining)),
+        _ => Err(input.seek_trivia().expected(format!("'{keyword}'"))),
+    }
+}
+
+fn parse_number(input: PositionedBuffer) -> ParserResult<PositionedBuffer, u64> {
+    let input = input.seek_trivia();
+    let matched = input
+        .buffer
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(input.buffer.len());
+
+    match input.buffer[0..matched].parse() {
+        Ok(number) => Ok((number, input.seek(matched))),
+        Err(_) => Err(input.expected("a number")),
+    }
+}
+
+pub fn number_parser<'a>() -> impl Parser<PositionedBuffer<'a>, Output = u64> + 'a {
+    parse_number
+}
+
 pub fn parse_literal<'a>(
     expected: &str,
     input: PositionedBuffer<'a>,
 ) -> ParserResult<PositionedBuffer<'a>, Symbol> {
-    let input = input.seek_whitespace();
+    let input = input.seek_trivia();
     if input.buffer.starts_with(expected) {
         Ok((
             input.buffer[0..expected.len()].to_string(),
             input.seek(expected.len()),
         ))
     } else {
-        Err(input.error(format!("Expected '{expected}' at this position.")))
+        Err(input.expected(format!("'{expected}'")))
     }
 }
 
@@ -54,10 +132,66 @@ pub fn literal_parser<'a>(
 
 #[cfg(test)]
 mod tests {
-    use std::assert_matches::assert_matches;
+    use std::assert_matches;
 
     use super::*;
 
+    #[test]
+    fn test_symbol() {
+        for symbol in ["x'", "f₁", "α", "succ?", "is-zero", "_x2", "x''", "Ωmega"] {
+            let input = PositionedBuffer::new(symbol);
+            assert_matches!(
+                symbol_parser().parse(input),
+                Ok((output, remaining)) if output == symbol && remaining.buffer.is_empty(),
+            );
+        }
+
+        let input = PositionedBuffer::new("x'y");
+        assert_matches!(symbol_parser().parse(input), Ok((output, _)) if output == "x'");
+        let input = PositionedBuffer::new("x--comment");
+        assert_matches!(symbol_parser().parse(input), Ok((output, _)) if output == "x");
+        let input = PositionedBuffer::new("xλy");
+        assert_matches!(symbol_parser().parse(input), Ok((output, _)) if output == "x");
+        let input = PositionedBuffer::new("x- y");
+        assert_matches!(symbol_parser().parse(input), Ok((output, _)) if output == "x");
+
+        for invalid in ["λx", "'x", "1x", "-x", "let", "in"] {
+            let input = PositionedBuffer::new(invalid);
+            assert_matches!(symbol_parser().parse(input), Err(error) if error.span.start == 0);
+        }
+        let input = PositionedBuffer::new("letter");
+        assert_matches!(symbol_parser().parse(input), Ok((output, _)) if output == "letter");
+        let input = PositionedBuffer::new("in");
+        assert_matches!(word_parser().parse(input), Ok((output, _)) if output == "in");
+    }
+
+    #[test]
+    fn test_keyword() {
+        let input = PositionedBuffer::new(" let x");
+        assert_matches!(
+            keyword_parser("let").parse(input),
+            Ok((output, remaining)) if output == "let" && remaining.buffer == " x",
+        );
+
+        let input = PositionedBuffer::new("letter");
+        assert_matches!(
+            keyword_parser("let").parse(input),
+            Err(error) if error.to_string() == "expected 'let'" && error.span == (0..1),
+        );
+    }
+
+    #[test]
+    fn test_number() {
+        let input = PositionedBuffer::new(" 42x");
+        assert_matches!(
+            number_parser().parse(input),
+            Ok((42, remaining)) if remaining.buffer == "x",
+        );
+
+        let input = PositionedBuffer::new("x42");
+        assert_matches!(number_parser().parse(input), Err(_));
+    }
+
     #[test]
     fn test_literal() {
         let literal_parser = literal_parser("hello");
@@ -0,0 +1,199 @@
+use f_prime_parser::combinators::{sep_by1, spanned};
+use f_prime_parser::{Parser, ParserInput, ParserResult, ThenParserExtensions};
+
+use crate::expression::buffer::{Parsable, PositionedBuffer};
+use crate::expression::source::Span;
+use crate::expression::symbol::literal_parser;
+use crate::expression::Expression;
+
+/// `(first, second, ...)`, with at least two elements.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Tuple {
+    pub elements: Vec<Expression>,
+    pub span: Span,
+}
+
+impl Tuple {
+    /// Parses the comma separated elements and the closing parenthesis after `(`.
+    pub fn elements_parser<'a>() -> impl Parser<PositionedBuffer<'a>, Output = Vec<Expression>> + 'a
+    {
+        sep_by1(Expression::parser(), literal_parser(",")).then_skip(literal_parser(")"))
+    }
+
+    /// The tuple of the elements spanning `span`, or the expression itself if there is only one,
+    /// since `(e)` is only a grouping.
+    pub fn group(mut elements: Vec<Expression>, span: impl Into<Span>) -> Expression {
+        if elements.len() == 1 {
+            elements.remove(0)
+        } else {
+            Expression::from(Tuple {
+                elements,
+                span: span.into(),
+            })
+        }
+    }
+}
+
+impl TryFrom<Expression> for Tuple {
+    type Error = ();
+
+    fn try_from(value: Expression) -> Result<Self, Self::Error> {
+        if let Expression::Tuple(tuple) = value {
+            Ok(*tuple)
+        } else {
+            Err(())
+        }
+    }
+}
+
+impl Parsable for Tuple {
+    fn parse(input: PositionedBuffer) -> ParserResult<PositionedBuffer, Self> {
+        let parser = literal_parser("(").skip_then(Tuple::elements_parser());
+        let parser = spanned(parser).map(|(elements, span)| Tuple::group(elements, span));
+        match parser.parse(input.clone())? {
+            (Expression::Tuple(tuple), remaining) => Ok((*tuple, remaining)),
+            _ => Err(input.seek_trivia().expected("a tuple")),
+        }
+    }
+}
+
+/// `tuple.index`, the element of a tuple at an index starting from 1.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Projection {
+    pub tuple: Expression,
+    pub index: usize,
+    pub span: Span,
+}
+
+fn parse_index(input: PositionedBuffer) -> ParserResult<PositionedBuffer, usize> {
+    let digits = match input.buffer.strip_prefix('.') {
+        Some(rest) => rest
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(rest.len()),
+        None => 0,
+    };
+    match input.buffer.get(1..1 + digits).map(str::parse) {
+        Some(Ok(index)) if digits > 0 => Ok((index, input.seek(1 + digits))),
+        _ => Err(input.expected("a projection")),
+    }
+}
+
+impl Projection {
+    /// Parses the projections `.index` right after `tuple`, as in `t.1.2`. There is no whitespace
+    /// before the dots, so that the one of `λx. 1` still ends the parameters. Types have none,
+    /// see `PositionedBuffer::in_type`.
+    pub fn parse_postfix(
+        mut tuple: Expression,
+        mut input: PositionedBuffer,
+    ) -> ParserResult<PositionedBuffer, Expression> {
+        if !input.projections() {
+            return Ok((tuple, input));
+        }
+        while let Ok((index, remaining)) = parse_index(input.clone()) {
+            let span = tuple
+                .span()
+                .join(&Span::new(input.position..remaining.position));
+            tuple = Expression::from(Projection { tuple, index, span });
+            input = remaining;
+        }
+        Ok((tuple, input))
+    }
+
+    /// The projected element, if the expression is a tuple long enough.
+    pub fn element(&self) -> Option<&Expression> {
+        match &self.tuple {
+            Expression::Tuple(tuple) => tuple.elements.get(self.index.checked_sub(1)?),
+            _ => None,
+        }
+    }
+}
+
+impl TryFrom<Expression> for Projection {
+    type Error = ();
+
+    fn try_from(value: Expression) -> Result<Self, Self::Error> {
+        if let Expression::Projection(projection) = value {
+            Ok(*projection)
+        } else {
+            Err(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::assert_matches;
+
+    use super::*;
+    use crate::expression::abstraction::TypedAbstraction;
+    use crate::expression::application::Application;
+    use crate::expression::variable::Variable;
+
+    #[test]
+    fn test_tuple() {
+        let input = PositionedBuffer::new("(f x, λy. y, (a, b))");
+        let (tuple, remaining) = Tuple::parse(input).unwrap();
+        assert!(remaining.buffer.is_empty());
+        assert_eq!(tuple.span.range(), Some(0..21));
+        assert_eq!(tuple.elements.len(), 3);
+        assert_matches!(tuple.elements[0], Expression::Application(_));
+        assert_matches!(tuple.elements[1], Expression::Abstraction(_));
+        assert_matches!(&tuple.elements[2], Expression::Tuple(pair) if pair.elements.len() == 2);
+
+        let input = PositionedBuffer::new("(x)");
+        assert_matches!(Tuple::parse(input), Err(error) if error.to_string() == "expected a tuple");
+        let input = PositionedBuffer::new("(x, )");
+        assert_eq!(Tuple::parse(input).unwrap_err().span, 4..5);
+    }
+
+    #[test]
+    fn test_projection() {
+        let input = PositionedBuffer::new("(a, b, c).2 .1");
+        let (expression, remaining) = Expression::parse(input).unwrap();
+        assert_eq!(remaining.buffer, " .1");
+        let projection = Projection::try_from(expression).unwrap();
+        assert_eq!(projection.index, 2);
+        assert_eq!(projection.span.range(), Some(0..11));
+        assert_eq!(
+            projection.element(),
+            Some(&Expression::from(Variable::from("b".to_string())))
+        );
+
+        let input = PositionedBuffer::new("f x.1.2");
+        let (expression, _) = Expression::parse_complete(input).unwrap();
+        let application = Application::try_from(expression).unwrap();
+        let projection = Projection::try_from(application.argument).unwrap();
+        assert_eq!(projection.index, 2);
+        assert_matches!(projection.tuple, Expression::Projection(_));
+        assert_eq!(
+            Projection {
+                index: 0,
+                ..projection
+            }
+            .element(),
+            None
+        );
+
+        let input = PositionedBuffer::new("1.2");
+        let (expression, _) = Expression::parse_complete(input).unwrap();
+        let projection = Projection::try_from(expression).unwrap();
+        assert_eq!(projection.index, 2);
+        assert_eq!(projection.span.range(), Some(0..3));
+        assert_matches!(projection.tuple, Expression::Abstraction(_));
+    }
+
+    #[test]
+    fn test_projection_after_type() {
+        let input = PositionedBuffer::new("(λx:T.0) a");
+        let (expression, _) = Expression::parse_complete(input).unwrap();
+        let application = Application::try_from(expression).unwrap();
+        let abstraction = TypedAbstraction::try_from(application.applicator).unwrap();
+        assert_matches!(abstraction.parameter_type, Expression::Variable(_));
+        assert_matches!(abstraction.body, Expression::Abstraction(_));
+
+        let input = PositionedBuffer::new("λx:T.x.1");
+        let (expression, _) = Expression::parse_complete(input).unwrap();
+        let abstraction = TypedAbstraction::try_from(expression).unwrap();
+        assert_matches!(abstraction.body, Expression::Projection(_));
+    }
+}
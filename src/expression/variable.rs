@@ -1,7 +1,9 @@
-use f_prime_parser::{Parser, ParserResult};
+use f_prime_parser::combinators::spanned;
+use f_prime_parser::{Parser, ParserInput, ParserResult};
 
 use crate::expression::buffer::Parsable;
 use crate::expression::buffer::PositionedBuffer;
+use crate::expression::source::Span;
 use crate::expression::symbol::{symbol_parser, Symbol};
 use crate::expression::Expression;
 
@@ -10,6 +12,7 @@ pub type DeBruijnIndex = u64;
 pub struct Variable {
     pub symbol: Symbol,
     pub index: DeBruijnIndex,
+    pub span: Span,
 }
 
 impl From<Symbol> for Variable {
@@ -17,6 +20,7 @@ impl From<Symbol> for Variable {
         Variable {
             symbol: value,
             index: 0,
+            span: Span::default(),
         }
     }
 }
@@ -33,16 +37,31 @@ impl TryFrom<Expression> for Variable {
     }
 }
 
+/// Parses a symbol which is not a constant of the input.
+fn parse_name(input: PositionedBuffer) -> ParserResult<PositionedBuffer, Symbol> {
+    let (symbol, remaining) = symbol_parser().parse(input.clone())?;
+    if input.constants().contains(&symbol.as_str()) {
+        let message = format!("'{symbol}' is a constant of the language");
+        return Err(input.seek_trivia().error(message));
+    }
+    Ok((symbol, remaining))
+}
+
 impl Parsable for Variable {
     fn parse(input: PositionedBuffer) -> ParserResult<PositionedBuffer, Self> {
-        let parser = symbol_parser().map(Variable::from);
+        let parser = spanned(parse_name)
+            .map(|(symbol, span)| Variable {
+                span: Span::new(span),
+                ..Variable::from(symbol)
+            })
+            .label("a variable");
         parser.parse(input)
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use std::assert_matches::assert_matches;
+    use std::assert_matches;
 
     use super::*;
 
@@ -51,9 +70,18 @@ mod tests {
         let input = PositionedBuffer::new("x y");
         assert_matches!(
             Variable::parse(input),
-            Ok((variable, _)) if variable.symbol == "x",
+            Ok((variable, _)) if variable.symbol == "x" && variable.span.range() == Some(0..1),
         );
 
+        let input = PositionedBuffer::new("f₁' x");
+        assert_matches!(
+            Variable::parse(input),
+            Ok((variable, _)) if variable.symbol == "f₁'" && variable.span.range() == Some(0..5),
+        );
+
+        let input = PositionedBuffer::new("in");
+        assert_matches!(Variable::parse(input), Err(_),);
+
         let input = PositionedBuffer::new("->");
         assert_matches!(Variable::parse(input), Err(_),);
     }
@@ -1 +1,2 @@
+pub mod arith;
 pub mod untyped;
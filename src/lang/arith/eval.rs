@@ -0,0 +1,230 @@
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::slice;
+
+use crate::eval::BetaReduction;
+use crate::expression::application::Application;
+use crate::expression::conditional::Conditional;
+use crate::expression::numeral::{NumeralEncoding, SUCCESSOR};
+use crate::expression::Expression;
+use crate::lang::arith::term::ArithTerm;
+use crate::term::Term;
+use crate::traverse::pretty_print::ExpressionPrettyPrinter;
+
+/// A term no evaluation rule applies to, which is not a value either, such as `succ true`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct StuckTerm {
+    pub term: Expression,
+}
+
+impl Display for StuckTerm {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let term = ExpressionPrettyPrinter::format_readback(&self.term, NumeralEncoding::Unary);
+        write!(f, "stuck at {term}")
+    }
+}
+
+impl Error for StuckTerm {}
+
+/// Evaluates one step at a time with the rules of figures 3-1 and 3-2 of TAPL.
+pub struct SmallStepEvaluator;
+
+impl SmallStepEvaluator {
+    /// Takes a single step, returning false if the expression is a value or stuck.
+    pub fn step(expression: &mut Expression) -> bool {
+        match expression {
+            Expression::Conditional(conditional) => {
+                // E-IfTrue and E-IfFalse, or else E-If.
+                if let Some(branch) = conditional.branch() {
+                    *expression = branch.clone();
+                    return true;
+                }
+                Self::step(&mut conditional.condition)
+            }
+            Expression::Application(application) => {
+                // E-Succ, E-Pred and E-IsZero.
+                if Self::step(&mut application.argument) {
+                    return true;
+                }
+                let Expression::Constant(operator) = &application.applicator else {
+                    return false;
+                };
+                // E-PredZero, E-PredSucc, E-IsZeroZero and E-IsZeroSucc.
+                match ArithTerm::delta(operator, slice::from_ref(&application.argument)) {
+                    Some(result) => {
+                        *expression = result;
+                        true
+                    }
+                    None => false,
+                }
+            }
+            _ => false,
+        }
+    }
+
+    /// Steps until no rule applies, failing if the term ends up stuck instead of a value.
+    pub fn evaluate(term: &mut ArithTerm) -> Result<(), StuckTerm> {
+        while Self::step(&mut term.expression) {}
+        if ArithTerm::is_value(&term.expression) {
+            Ok(())
+        } else {
+            Err(StuckTerm {
+                term: term.expression.clone(),
+            })
+        }
+    }
+}
+
+impl BetaReduction<ArithTerm> for SmallStepEvaluator {
+    fn reduce_once(term: &mut ArithTerm) -> bool {
+        Self::step(&mut term.expression)
+    }
+}
+
+/// Evaluates a term straight to its value with the rules of exercise 3.5.17 of TAPL.
+pub struct BigStepEvaluator;
+
+impl BigStepEvaluator {
+    /// The value of the term, or the innermost subterm, with its subterms evaluated, that has none.
+    pub fn evaluate(term: &ArithTerm) -> Result<Expression, StuckTerm> {
+        Self::evaluate_expression(&term.expression)
+    }
+
+    fn evaluate_expression(expression: &Expression) -> Result<Expression, StuckTerm> {
+        if ArithTerm::is_value(expression) {
+            // B-Value.
+            return Ok(expression.clone());
+        }
+        match expression {
+            Expression::Conditional(conditional) => {
+                // B-IfTrue and B-IfFalse.
+                let condition = Self::evaluate_expression(&conditional.condition)?;
+                let evaluated = Conditional {
+                    condition,
+                    ..(**conditional).clone()
+                };
+                match evaluated.branch() {
+                    Some(branch) => Self::evaluate_expression(branch),
+                    None => Err(StuckTerm {
+                        term: Expression::from(evaluated),
+                    }),
+                }
+            }
+            Expression::Application(application) => {
+                let applicator = match &application.applicator {
+                    Expression::Constant(_) => application.applicator.clone(),
+                    applicator => Self::evaluate_expression(applicator)?,
+                };
+                let argument = Self::evaluate_expression(&application.argument)?;
+                let operator = match &applicator {
+                    Expression::Constant(operator) => Some(operator),
+                    _ => None,
+                };
+                // B-Succ.
+                if operator.is_some_and(|operator| operator.symbol == SUCCESSOR)
+                    && ArithTerm::is_numeric_value(&argument)
+                {
+                    return Ok(Expression::from(Application {
+                        applicator,
+                        argument,
+                        span: application.span.clone(),
+                    }));
+                }
+                // B-PredZero, B-PredSucc, B-IsZeroZero and B-IsZeroSucc.
+                let result = operator
+                    .and_then(|operator| ArithTerm::delta(operator, slice::from_ref(&argument)));
+                result.ok_or_else(|| StuckTerm {
+                    term: Expression::from(Application {
+                        applicator,
+                        argument,
+                        span: application.span.clone(),
+                    }),
+                })
+            }
+            _ => Err(StuckTerm {
+                term: expression.clone(),
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::eval::TracingBetaReduction;
+    use crate::expression::buffer::{Parsable, PositionedBuffer};
+
+    use super::*;
+
+    fn term(source: &str) -> ArithTerm {
+        ArithTerm::parse_complete(PositionedBuffer::new(source))
+            .unwrap()
+            .0
+    }
+
+    fn readback(expression: &Expression) -> String {
+        ExpressionPrettyPrinter::format_readback(expression, NumeralEncoding::Unary)
+    }
+
+    #[test]
+    fn test_small_step() {
+        let mut term = term("if iszero (pred 1) then succ (pred 2) else 0");
+        let trace = SmallStepEvaluator::trace_with(&mut term, readback);
+        assert_eq!(
+            trace,
+            vec![
+                "if iszero 0 then succ (pred 2) else 0",
+                "if true then succ (pred 2) else 0",
+                "succ (pred 2)",
+                "2"
+            ]
+        );
+        assert_eq!(SmallStepEvaluator::evaluate(&mut term), Ok(()));
+    }
+
+    #[test]
+    fn test_small_step_stuck() {
+        let mut term = term("if iszero (pred true) then 0 else 1");
+        let error = SmallStepEvaluator::evaluate(&mut term).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "stuck at if iszero (pred true) then 0 else 1"
+        );
+
+        let mut term = self::term("succ (if 0 then true else false)");
+        let error = SmallStepEvaluator::evaluate(&mut term).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "stuck at succ (if 0 then true else false)"
+        );
+    }
+
+    #[test]
+    fn test_big_step() {
+        for source in [
+            "if iszero (pred 1) then succ (pred 2) else 0",
+            "iszero (if false then 0 else succ 3)",
+            "pred (pred (pred 1))",
+            "if true then false else 0",
+        ] {
+            let mut small = term(source);
+            SmallStepEvaluator::evaluate(&mut small).unwrap();
+            let big = BigStepEvaluator::evaluate(&term(source)).unwrap();
+            assert_eq!(readback(&big), readback(&small.expression), "{source}");
+        }
+    }
+
+    #[test]
+    fn test_big_step_stuck() {
+        let error = BigStepEvaluator::evaluate(&term("if iszero (pred true) then 0 else 1"));
+        assert_eq!(error.unwrap_err().to_string(), "stuck at pred true");
+
+        let error = BigStepEvaluator::evaluate(&term("succ (if iszero 0 then 1 else 2) false"));
+        assert_eq!(error.unwrap_err().to_string(), "stuck at 2 false");
+
+        let error = BigStepEvaluator::evaluate(&term("if succ 0 then true else false"));
+        assert_eq!(
+            error.unwrap_err().to_string(),
+            "stuck at if 1 then true else false"
+        );
+    }
+}
@@ -0,0 +1,176 @@
+use f_prime_parser::{Parser, ParserResult};
+
+use crate::expression::buffer::{Parsable, PositionedBuffer};
+use crate::expression::conditional::{FALSE, TRUE};
+use crate::expression::constant::{
+    constant, constant_symbol, is_constant, Constant, DefinedConstants,
+};
+use crate::expression::numeral::{NumeralEncoding, SUCCESSOR, ZERO};
+use crate::expression::Expression;
+use crate::term::Term;
+
+pub const PREDECESSOR: &str = "pred";
+pub const IS_ZERO: &str = "iszero";
+
+/// A term of the language of booleans and numbers from chapter 3 of TAPL:
+/// `true`, `false`, `if t then t else t`, `0`, `succ t`, `pred t` and `iszero t`.
+/// Number literals stand for `succ` applied to `0` as many times.
+pub struct ArithTerm {
+    pub expression: Expression,
+}
+
+impl DefinedConstants for ArithTerm {
+    const CHOICES: &'static [&'static str] = &[TRUE, FALSE, SUCCESSOR, PREDECESSOR, IS_ZERO];
+}
+
+impl ArithTerm {
+    pub fn new(expression: Expression) -> Self {
+        ArithTerm { expression }
+    }
+
+    /// Whether the expression is `0` or `succ` applied to a numeric value.
+    pub fn is_numeric_value(expression: &Expression) -> bool {
+        NumeralEncoding::Unary.decode(expression).is_some()
+    }
+
+    pub fn is_value(expression: &Expression) -> bool {
+        matches!(constant_symbol(expression), Some(TRUE | FALSE))
+            || Self::is_numeric_value(expression)
+    }
+
+    /// The operator and the operand of `succ t`, `pred t` or `iszero t`.
+    pub fn operation(expression: &Expression) -> Option<(&str, &Expression)> {
+        let Expression::Application(application) = expression else {
+            return None;
+        };
+        let operator = constant_symbol(&application.applicator)?;
+        [SUCCESSOR, PREDECESSOR, IS_ZERO]
+            .contains(&operator)
+            .then_some((operator, &application.argument))
+    }
+
+    fn validate(expression: &Expression) -> bool {
+        if let Some((_, operand)) = Self::operation(expression) {
+            return Self::validate(operand);
+        }
+        match expression {
+            Expression::Constant(constant) => {
+                [TRUE, FALSE, ZERO].contains(&constant.symbol.as_str())
+            }
+            Expression::Conditional(conditional) => {
+                Self::validate(&conditional.condition)
+                    && Self::validate(&conditional.consequent)
+                    && Self::validate(&conditional.alternative)
+            }
+            _ => false,
+        }
+    }
+}
+
+impl Parsable for ArithTerm {
+    fn parse(input: PositionedBuffer) -> ParserResult<PositionedBuffer, Self> {
+        let input = input
+            .with_constants::<ArithTerm>()
+            .with_numerals(NumeralEncoding::Unary)
+            .with_conditionals();
+        Expression::parser().map(ArithTerm::new).parse(input)
+    }
+}
+
+impl Term for ArithTerm {
+    fn as_expr(&self) -> &Expression {
+        &self.expression
+    }
+
+    fn as_expr_mut(&mut self) -> &mut Expression {
+        &mut self.expression
+    }
+
+    fn validate(&self) -> bool {
+        Self::validate(&self.expression)
+    }
+
+    fn delta(operator: &Constant, arguments: &[Expression]) -> Option<Expression> {
+        let [operand] = arguments else {
+            return None;
+        };
+        match operator.symbol.as_str() {
+            PREDECESSOR if is_constant(operand, ZERO) => Some(constant(ZERO)),
+            IS_ZERO if is_constant(operand, ZERO) => Some(constant(TRUE)),
+            PREDECESSOR if Self::is_numeric_value(operand) => {
+                Self::operation(operand).map(|(_, predecessor)| predecessor.clone())
+            }
+            IS_ZERO if Self::is_numeric_value(operand) => Some(constant(FALSE)),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::assert_matches;
+
+    use super::*;
+    use crate::eval::by_value::CallByValueEvaluator;
+    use crate::eval::BetaReduction;
+    use crate::traverse::pretty_print::ExpressionPrettyPrinter;
+
+    fn parse(source: &str) -> ArithTerm {
+        ArithTerm::parse_complete(PositionedBuffer::new(source))
+            .unwrap()
+            .0
+    }
+
+    #[test]
+    fn test_arith_valid() {
+        let term = parse("if iszero (pred 2) then succ 0 else false");
+        assert!(term.validate());
+        let conditional = match &term.expression {
+            Expression::Conditional(conditional) => conditional,
+            expression => panic!("not a conditional: {expression:?}"),
+        };
+        assert_matches!(
+            ArithTerm::operation(&conditional.condition),
+            Some((IS_ZERO, _))
+        );
+        assert_eq!(
+            ExpressionPrettyPrinter::format_named(&term.expression),
+            "if iszero (pred (succ (succ 0))) then succ 0 else false"
+        );
+        assert!(ArithTerm::is_value(&parse("succ (succ 0)").expression));
+        assert!(!ArithTerm::is_value(&parse("succ true").expression));
+    }
+
+    #[test]
+    fn test_arith_invalid() {
+        assert!(!parse("(λx. x) true").validate());
+        assert!(!parse("succ x").validate());
+        assert!(!parse("succ").validate());
+        assert!(!parse("succ 0 0").validate());
+        assert!(ArithTerm::parse_complete(PositionedBuffer::new("λsucc. 0")).is_err());
+    }
+
+    #[test]
+    fn test_arith_delta() {
+        let mut term = parse("pred (succ (pred 3))");
+        CallByValueEvaluator::reduce(&mut term);
+        assert_eq!(
+            ExpressionPrettyPrinter::format_readback(&term.expression, NumeralEncoding::Unary),
+            "2"
+        );
+
+        let mut term = parse("iszero (pred (pred 1))");
+        CallByValueEvaluator::reduce(&mut term);
+        assert_eq!(
+            ExpressionPrettyPrinter::format_named(&term.expression),
+            "true"
+        );
+
+        let mut term = parse("if iszero (pred 1) then succ (pred 2) else 0");
+        CallByValueEvaluator::reduce(&mut term);
+        assert_eq!(
+            ExpressionPrettyPrinter::format_readback(&term.expression, NumeralEncoding::Unary),
+            "2"
+        );
+    }
+}
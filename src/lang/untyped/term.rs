@@ -1,5 +1,10 @@
+use crate::expression::abstraction::{Abstraction, TypedAbstraction};
+use crate::expression::binding::Let;
+use crate::expression::program::Program;
+use crate::expression::variable::DeBruijnIndex;
 use crate::expression::Expression;
 use crate::term::Term;
+use crate::traverse::de_bruijn::shift::DeBruijnShift;
 
 pub struct UntypedLambdaTerm {
     pub expression: Expression,
@@ -17,6 +22,11 @@ impl UntypedLambdaTerm {
             Expression::Application(application) => {
                 Self::validate(&application.applicator) && Self::validate(&application.argument)
             }
+            Expression::Let(binding) => {
+                Self::validate(&binding.value) && Self::validate(&binding.body)
+            }
+            Expression::Tuple(tuple) => tuple.elements.iter().all(Self::validate),
+            Expression::Projection(projection) => Self::validate(&projection.tuple),
             _ => false,
         }
     }
@@ -36,10 +46,110 @@ impl Term for UntypedLambdaTerm {
     }
 }
 
+/// An untyped term whose free variables may refer to top-level definitions by name,
+/// which are only unfolded when they are applied.
+pub struct UntypedProgramTerm {
+    /// The bodies of the definitions, each in the scope of the ones before it.
+    pub definitions: Vec<Expression>,
+    pub expression: Expression,
+}
+
+impl UntypedProgramTerm {
+    /// The program must have been converted with `DeBruijnConverter::convert_program`.
+    pub fn new(program: Program) -> Option<Self> {
+        let definitions = program
+            .definitions
+            .into_iter()
+            .map(|definition| definition.body)
+            .collect();
+        let expression = program.main?;
+        Some(UntypedProgramTerm {
+            definitions,
+            expression,
+        })
+    }
+
+    /// Replaces the leftmost outermost reference to a definition which is applied,
+    /// or the whole expression if it is a reference, with the body of the definition.
+    pub fn unfold(&mut self) -> bool {
+        Self::unfold_head(&self.definitions, &mut self.expression, 0, true)
+    }
+
+    fn unfold_head(
+        definitions: &[Expression],
+        expression: &mut Expression,
+        depth: DeBruijnIndex,
+        head: bool,
+    ) -> bool {
+        match expression {
+            Expression::Variable(variable) if head => {
+                let count = definitions.len() as DeBruijnIndex;
+                let Some(reference) = variable.index.checked_sub(depth) else {
+                    return false;
+                };
+                if !(1..=count).contains(&reference) {
+                    return false;
+                }
+                let mut body = definitions[(count - reference) as usize].clone();
+                DeBruijnShift::shift(variable.index as i64, &mut body);
+                *expression = body;
+                true
+            }
+            Expression::Abstraction(box Abstraction { body, .. })
+            | Expression::TypedAbstraction(box TypedAbstraction { body, .. }) => {
+                Self::unfold_head(definitions, body, depth + 1, false)
+            }
+            Expression::Application(application) => {
+                Self::unfold_head(definitions, &mut application.applicator, depth, true)
+                    || Self::unfold_head(definitions, &mut application.argument, depth, false)
+            }
+            Expression::Let(box Let {
+                value,
+                body,
+                recursive,
+                ..
+            }) => {
+                let value_depth = depth + *recursive as DeBruijnIndex;
+                Self::unfold_head(definitions, value, value_depth, false)
+                    || Self::unfold_head(definitions, body, depth + 1, false)
+            }
+            Expression::Conditional(conditional) => {
+                Self::unfold_head(definitions, &mut conditional.condition, depth, false)
+                    || Self::unfold_head(definitions, &mut conditional.consequent, depth, false)
+                    || Self::unfold_head(definitions, &mut conditional.alternative, depth, false)
+            }
+            Expression::Tuple(tuple) => tuple
+                .elements
+                .iter_mut()
+                .any(|element| Self::unfold_head(definitions, element, depth, false)),
+            Expression::Projection(projection) => {
+                Self::unfold_head(definitions, &mut projection.tuple, depth, false)
+            }
+            Expression::Variable(_) | Expression::Constant(_) | Expression::Error(_) => false,
+        }
+    }
+}
+
+impl Term for UntypedProgramTerm {
+    fn as_expr(&self) -> &Expression {
+        &self.expression
+    }
+
+    fn as_expr_mut(&mut self) -> &mut Expression {
+        &mut self.expression
+    }
+
+    fn validate(&self) -> bool {
+        self.definitions.iter().all(UntypedLambdaTerm::validate)
+            && UntypedLambdaTerm::validate(&self.expression)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::expression::buffer::{Parsable, PositionedBuffer};
     use crate::traverse::de_bruijn::convert::DeBruijnConverter;
+    use crate::traverse::pretty_print::ExpressionPrettyPrinter;
 
     use super::*;
 
@@ -50,6 +160,12 @@ mod tests {
         DeBruijnConverter::convert(&mut expression);
         let term = UntypedLambdaTerm::new(expression);
         assert!(term.validate());
+
+        let input = PositionedBuffer::new("(λp. p.2) (λx. x, (λy. y, λz. z).1)");
+        let mut expression = Expression::parse(input).unwrap().0;
+        DeBruijnConverter::convert(&mut expression);
+        let term = UntypedLambdaTerm::new(expression);
+        assert!(term.validate());
     }
 
     #[test]
@@ -59,5 +175,26 @@ mod tests {
         DeBruijnConverter::convert(&mut expression);
         let term = UntypedLambdaTerm::new(expression);
         assert!(!term.validate());
+
+        let input = PositionedBuffer::new("(λx. x, (λy: U. y).1)");
+        let mut expression = Expression::parse(input).unwrap().0;
+        DeBruijnConverter::convert(&mut expression);
+        let term = UntypedLambdaTerm::new(expression);
+        assert!(!term.validate());
+    }
+
+    #[test]
+    fn test_unfold() {
+        let input = PositionedBuffer::new("id = λx. x;\nself = λx. x x;\nλy. self (y id)");
+        let (mut program, _) = Program::parse_complete(input).unwrap();
+        DeBruijnConverter::convert_program(&mut program);
+        let mut term = UntypedProgramTerm::new(program).unwrap();
+        assert!(term.validate());
+        assert!(term.unfold());
+        assert_eq!(
+            ExpressionPrettyPrinter::format_indexed(&term.expression),
+            "λ (λ 1 1) (1 3)"
+        );
+        assert!(!term.unfold());
     }
 }
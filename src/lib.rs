@@ -1,6 +1,6 @@
-#![feature(assert_matches)]
 #![feature(box_patterns)]
 
+pub mod diagnostics;
 pub mod eval;
 pub mod expression;
 mod lang;
@@ -1,10 +1,25 @@
+use crate::expression::constant::Constant;
 use crate::expression::Expression;
+pub use crate::lang::arith::term as arith;
 pub use crate::lang::untyped::term as untyped;
 
+/// Rewrites a constant applied to arguments, see `Term::delta`.
+pub type DeltaRule = fn(&Constant, &[Expression]) -> Option<Expression>;
+
 pub trait Term {
     fn as_expr(&self) -> &Expression;
 
     fn as_expr_mut(&mut self) -> &mut Expression;
 
     fn validate(&self) -> bool;
+
+    /// The delta rules of the language: what `constant` applied to `arguments` reduces to,
+    /// e.g. `succ 0` to `1`, or `None` if it does not reduce. There are none by default.
+    fn delta(constant: &Constant, arguments: &[Expression]) -> Option<Expression>
+    where
+        Self: Sized,
+    {
+        let _ = (constant, arguments);
+        None
+    }
 }
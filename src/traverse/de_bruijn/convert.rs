@@ -1,6 +1,8 @@
 use std::collections::HashMap;
 
 use crate::expression::abstraction::{Abstraction, TypedAbstraction};
+use crate::expression::binding::Let;
+use crate::expression::program::Program;
 use crate::expression::symbol::Symbol;
 use crate::expression::variable::DeBruijnIndex;
 use crate::expression::Expression;
@@ -17,6 +19,26 @@ impl DeBruijnConverter {
         converter.traverse(expression, 0);
     }
 
+    /// Converts every definition in the scope of the ones before it,
+    /// and the main expression in the scope of all of them, as if they were nested `let`s.
+    /// Free variables are numbered once for the whole program.
+    pub fn convert_program(program: &mut Program) {
+        let mut converter = DeBruijnConverter::default();
+        let mut scope = 0;
+        for definition in &mut program.definitions {
+            converter.traverse(&mut definition.body, scope);
+            converter
+                .variable_context
+                .entry(definition.name.symbol.clone())
+                .or_default()
+                .push(scope as i64);
+            scope += 1;
+        }
+        if let Some(main) = &mut program.main {
+            converter.traverse(main, scope);
+        }
+    }
+
     fn traverse(&mut self, expression: &mut Expression, current_scope: DeBruijnIndex) {
         match expression {
             Expression::Variable(variable) => {
@@ -37,7 +59,9 @@ impl DeBruijnConverter {
                 };
                 variable.index = (current_scope as i64 - binding_scope) as DeBruijnIndex;
             }
-            Expression::Abstraction(box Abstraction { parameter, body })
+            Expression::Abstraction(box Abstraction {
+                parameter, body, ..
+            })
             | Expression::TypedAbstraction(box TypedAbstraction {
                 parameter, body, ..
             }) => {
@@ -55,6 +79,43 @@ impl DeBruijnConverter {
                 self.traverse(&mut application.applicator, current_scope);
                 self.traverse(&mut application.argument, current_scope);
             }
+            Expression::Let(box Let {
+                variable,
+                value,
+                body,
+                recursive,
+                ..
+            }) => {
+                if !*recursive {
+                    self.traverse(value, current_scope);
+                }
+                self.variable_context
+                    .entry(variable.symbol.clone())
+                    .or_default()
+                    .push(current_scope as i64);
+                if *recursive {
+                    self.traverse(value, current_scope + 1);
+                }
+                self.traverse(body, current_scope + 1);
+                self.variable_context
+                    .get_mut(&variable.symbol)
+                    .unwrap()
+                    .pop();
+            }
+            Expression::Conditional(conditional) => {
+                self.traverse(&mut conditional.condition, current_scope);
+                self.traverse(&mut conditional.consequent, current_scope);
+                self.traverse(&mut conditional.alternative, current_scope);
+            }
+            Expression::Tuple(tuple) => {
+                for element in &mut tuple.elements {
+                    self.traverse(element, current_scope);
+                }
+            }
+            Expression::Projection(projection) => {
+                self.traverse(&mut projection.tuple, current_scope);
+            }
+            Expression::Constant(_) | Expression::Error(_) => {}
         }
     }
 }
@@ -71,7 +132,7 @@ mod tests {
         let input = PositionedBuffer::new("a b c");
         let (mut expression, _) = Expression::parse(input).unwrap();
         DeBruijnConverter::convert(&mut expression);
-        let pretty = ExpressionPrettyPrinter::format_indexed(&mut expression);
+        let pretty = ExpressionPrettyPrinter::format_indexed(&expression);
         assert_eq!(pretty, "1 2 3");
     }
 
@@ -80,7 +141,7 @@ mod tests {
         let input = PositionedBuffer::new("b (λx.λy.b)");
         let (mut expression, _) = Expression::parse(input).unwrap();
         DeBruijnConverter::convert(&mut expression);
-        let pretty = ExpressionPrettyPrinter::format_indexed(&mut expression);
+        let pretty = ExpressionPrettyPrinter::format_indexed(&expression);
         assert_eq!(pretty, "1 (λ λ 3)");
     }
 
@@ -89,7 +150,7 @@ mod tests {
         let input = PositionedBuffer::new("(λx.λy.λz. w x y z)");
         let (mut expression, _) = Expression::parse(input).unwrap();
         DeBruijnConverter::convert(&mut expression);
-        let pretty = ExpressionPrettyPrinter::format_indexed(&mut expression);
+        let pretty = ExpressionPrettyPrinter::format_indexed(&expression);
         assert_eq!(pretty, "λ λ λ 4 3 2 1");
     }
 
@@ -98,7 +159,17 @@ mod tests {
         let input = PositionedBuffer::new("(λw. (λx. w x y) (λx. x))");
         let (mut expression, _) = Expression::parse(input).unwrap();
         DeBruijnConverter::convert(&mut expression);
-        let pretty = ExpressionPrettyPrinter::format_indexed(&mut expression);
+        let pretty = ExpressionPrettyPrinter::format_indexed(&expression);
         assert_eq!(pretty, "λ (λ 2 1 3) (λ 1)");
     }
+
+    #[test]
+    fn test_spans_preserved() {
+        let input = PositionedBuffer::new("λx. x y");
+        let (mut expression, _) = Expression::parse(input).unwrap();
+        DeBruijnConverter::convert(&mut expression);
+        let abstraction = Abstraction::try_from(expression).unwrap();
+        assert_eq!(abstraction.span.range(), Some(0..8));
+        assert_eq!(abstraction.body.span().range(), Some(5..8));
+    }
 }
@@ -1,4 +1,5 @@
 use crate::expression::abstraction::{Abstraction, TypedAbstraction};
+use crate::expression::binding::Let;
 use crate::expression::variable::DeBruijnIndex;
 use crate::expression::Expression;
 
@@ -19,7 +20,9 @@ impl DeBruijnShift {
                     variable.index = variable.index.saturating_add_signed(self.place);
                 }
             }
-            Expression::Abstraction(box Abstraction { parameter: _, body })
+            Expression::Abstraction(box Abstraction {
+                parameter: _, body, ..
+            })
             | Expression::TypedAbstraction(box TypedAbstraction {
                 parameter: _, body, ..
             }) => {
@@ -29,6 +32,27 @@ impl DeBruijnShift {
                 self.traverse(cutoff, &mut application.applicator);
                 self.traverse(cutoff, &mut application.argument);
             }
+            Expression::Let(box Let {
+                value,
+                body,
+                recursive,
+                ..
+            }) => {
+                self.traverse(cutoff + *recursive as DeBruijnIndex, value);
+                self.traverse(cutoff + 1, body);
+            }
+            Expression::Conditional(conditional) => {
+                self.traverse(cutoff, &mut conditional.condition);
+                self.traverse(cutoff, &mut conditional.consequent);
+                self.traverse(cutoff, &mut conditional.alternative);
+            }
+            Expression::Tuple(tuple) => {
+                for element in &mut tuple.elements {
+                    self.traverse(cutoff, element);
+                }
+            }
+            Expression::Projection(projection) => self.traverse(cutoff, &mut projection.tuple),
+            Expression::Constant(_) | Expression::Error(_) => {}
         }
     }
 }
@@ -47,7 +71,7 @@ mod tests {
         let (mut expression, _) = Expression::parse(input).unwrap();
         DeBruijnConverter::convert(&mut expression);
         DeBruijnShift::shift(2, &mut expression);
-        let pretty = ExpressionPrettyPrinter::format_indexed(&mut expression);
+        let pretty = ExpressionPrettyPrinter::format_indexed(&expression);
         assert_eq!(pretty, "λ λ 2 (1 5)");
     }
 
@@ -57,7 +81,7 @@ mod tests {
         let (mut expression, _) = Expression::parse(input).unwrap();
         DeBruijnConverter::convert(&mut expression);
         DeBruijnShift::shift(2, &mut expression);
-        let pretty = ExpressionPrettyPrinter::format_indexed(&mut expression);
+        let pretty = ExpressionPrettyPrinter::format_indexed(&expression);
         assert_eq!(pretty, "λ 1 4 (λ 1 2 5)");
     }
 }
@@ -1,4 +1,5 @@
 use crate::expression::abstraction::{Abstraction, TypedAbstraction};
+use crate::expression::binding::Let;
 use crate::expression::variable::DeBruijnIndex;
 use crate::expression::Expression;
 use crate::traverse::de_bruijn::shift::DeBruijnShift;
@@ -20,7 +21,9 @@ impl DeBruijnSubstitution {
                     *expression = self.replacement.clone()
                 }
             }
-            Expression::Abstraction(box Abstraction { parameter: _, body })
+            Expression::Abstraction(box Abstraction {
+                parameter: _, body, ..
+            })
             | Expression::TypedAbstraction(box TypedAbstraction {
                 parameter: _, body, ..
             }) => {
@@ -33,6 +36,35 @@ impl DeBruijnSubstitution {
                 self.traverse(target, &mut application.applicator);
                 self.traverse(target, &mut application.argument);
             }
+            Expression::Let(box Let {
+                value,
+                body,
+                recursive,
+                ..
+            }) => {
+                if !*recursive {
+                    self.traverse(target, value);
+                }
+                let replacement = self.replacement.clone();
+                DeBruijnShift::shift(1, &mut self.replacement);
+                if *recursive {
+                    self.traverse(target + 1, value);
+                }
+                self.traverse(target + 1, body);
+                self.replacement = replacement;
+            }
+            Expression::Conditional(conditional) => {
+                self.traverse(target, &mut conditional.condition);
+                self.traverse(target, &mut conditional.consequent);
+                self.traverse(target, &mut conditional.alternative);
+            }
+            Expression::Tuple(tuple) => {
+                for element in &mut tuple.elements {
+                    self.traverse(target, element);
+                }
+            }
+            Expression::Projection(projection) => self.traverse(target, &mut projection.tuple),
+            Expression::Constant(_) | Expression::Error(_) => {}
         }
     }
 }
@@ -54,7 +86,7 @@ mod tests {
 
         let replacement = Expression::from(Variable::from(String::from("a")));
         DeBruijnSubstitution::substitute(1, replacement, &mut expression);
-        let pretty = ExpressionPrettyPrinter::format_named(&mut expression);
+        let pretty = ExpressionPrettyPrinter::format_named(&expression);
         assert_eq!(pretty, "a (λx. λy. a)");
     }
 
@@ -69,7 +101,7 @@ mod tests {
         DeBruijnConverter::convert(&mut replacement);
 
         DeBruijnSubstitution::substitute(1, replacement, &mut expression);
-        let pretty = ExpressionPrettyPrinter::format_nameless_locals(&mut expression);
+        let pretty = ExpressionPrettyPrinter::format_nameless_locals(&expression);
         assert_eq!(pretty, "a (λ a) (λ a (λ a))");
     }
 }
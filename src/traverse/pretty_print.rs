@@ -1,4 +1,6 @@
 use crate::expression::abstraction::{Abstraction, TypedAbstraction};
+use crate::expression::binding::Let;
+use crate::expression::numeral::NumeralEncoding;
 use crate::expression::variable::DeBruijnIndex;
 use crate::expression::Expression;
 
@@ -10,6 +12,8 @@ enum PrinterMode {
 
 pub struct ExpressionPrettyPrinter {
     mode: PrinterMode,
+    /// Prints the subterms shaped like numerals of this encoding as numbers.
+    numerals: Option<NumeralEncoding>,
 }
 
 impl ExpressionPrettyPrinter {
@@ -25,18 +29,35 @@ impl ExpressionPrettyPrinter {
         Self::format_inner(expression, PrinterMode::NamelessLocals)
     }
 
+    /// Formats like `format_named`, reading back numerals, e.g. `λs. λz. s z` as `1`.
+    pub fn format_readback(expression: &Expression, numerals: NumeralEncoding) -> String {
+        let mut printer = ExpressionPrettyPrinter {
+            mode: PrinterMode::Named,
+            numerals: Some(numerals),
+        };
+        printer.traverse_last(expression, 0)
+    }
+
     pub fn format(expression: &Expression) -> String {
         Self::format_named(expression)
     }
 
-    fn format_inner(expression: &Expression, mode: PrinterMode) -> String {
-        let expression_is_abstraction = matches!(
+    /// Whether the expression extends as far to the right as possible, so that it is
+    /// formatted in parentheses unless it comes last.
+    fn is_open(expression: &Expression) -> bool {
+        matches!(
             expression,
-            Expression::Abstraction(_) | Expression::TypedAbstraction(_)
-        );
-        let mut printer = ExpressionPrettyPrinter { mode };
-        let string = printer.traverse(expression, 0);
-        if expression_is_abstraction {
+            Expression::Abstraction(_)
+                | Expression::TypedAbstraction(_)
+                | Expression::Let(_)
+                | Expression::Conditional(_)
+        )
+    }
+
+    /// Formats an expression that comes last, without the parentheses around open ones.
+    fn traverse_last(&mut self, expression: &Expression, current_scope: DeBruijnIndex) -> String {
+        let string = self.traverse(expression, current_scope);
+        if Self::is_open(expression) {
             string
                 .strip_prefix('(')
                 .and_then(|s| s.strip_suffix(')'))
@@ -47,7 +68,27 @@ impl ExpressionPrettyPrinter {
         }
     }
 
+    fn format_inner(expression: &Expression, mode: PrinterMode) -> String {
+        let mut printer = ExpressionPrettyPrinter {
+            mode,
+            numerals: None,
+        };
+        printer.traverse_last(expression, 0)
+    }
+
+    /// Whether the expression is read back as a number.
+    fn is_numeral(&self, expression: &Expression) -> bool {
+        self.numerals
+            .is_some_and(|numerals| numerals.decode(expression).is_some())
+    }
+
     fn traverse(&mut self, expression: &Expression, current_scope: DeBruijnIndex) -> String {
+        if let Some(number) = self
+            .numerals
+            .and_then(|numerals| numerals.decode(expression))
+        {
+            return number.to_string();
+        }
         let mut parameter_type = None;
         if let PrinterMode::Named = self.mode {
             if let Expression::TypedAbstraction(abstraction) = expression {
@@ -68,22 +109,13 @@ impl ExpressionPrettyPrinter {
                     }
                 }
             },
-            Expression::Abstraction(box Abstraction { parameter, body })
+            Expression::Abstraction(box Abstraction {
+                parameter, body, ..
+            })
             | Expression::TypedAbstraction(box TypedAbstraction {
                 parameter, body, ..
             }) => {
-                let body_is_abstraction = matches!(
-                    body,
-                    Expression::Abstraction(_) | Expression::TypedAbstraction(_)
-                );
-                let body = self.traverse(body, current_scope + 1);
-                let body = if body_is_abstraction {
-                    body.strip_prefix('(')
-                        .and_then(|s| s.strip_suffix(')'))
-                        .unwrap_or(body.as_str())
-                } else {
-                    body.as_str()
-                };
+                let body = self.traverse_last(body, current_scope + 1);
                 match self.mode {
                     PrinterMode::Named => {
                         if let Some(parameter_type) = parameter_type {
@@ -97,7 +129,8 @@ impl ExpressionPrettyPrinter {
             }
             Expression::Application(application) => {
                 let argument_is_application =
-                    matches!(application.argument, Expression::Application(_));
+                    matches!(application.argument, Expression::Application(_))
+                        && !self.is_numeral(&application.argument);
                 let applicator = self.traverse(&application.applicator, current_scope);
                 let argument = self.traverse(&application.argument, current_scope);
                 if argument_is_application {
@@ -106,6 +139,51 @@ impl ExpressionPrettyPrinter {
                     format!("{} {}", applicator, argument,)
                 }
             }
+            Expression::Let(box Let {
+                variable,
+                value,
+                body,
+                recursive,
+                ..
+            }) => {
+                let keyword = if *recursive { "letrec" } else { "let" };
+                let value = self.traverse_last(value, current_scope + *recursive as DeBruijnIndex);
+                let body = self.traverse_last(body, current_scope + 1);
+                match self.mode {
+                    PrinterMode::Named => {
+                        format!("({} {} = {} in {})", keyword, variable.symbol, value, body)
+                    }
+                    PrinterMode::Indexed | PrinterMode::NamelessLocals => {
+                        format!("({} {} in {})", keyword, value, body)
+                    }
+                }
+            }
+            Expression::Constant(constant) => constant.symbol.clone(),
+            Expression::Conditional(conditional) => {
+                let condition = self.traverse_last(&conditional.condition, current_scope);
+                let consequent = self.traverse_last(&conditional.consequent, current_scope);
+                let alternative = self.traverse_last(&conditional.alternative, current_scope);
+                format!("(if {condition} then {consequent} else {alternative})")
+            }
+            Expression::Tuple(tuple) => {
+                let elements = tuple
+                    .elements
+                    .iter()
+                    .map(|element| self.traverse_last(element, current_scope))
+                    .collect::<Vec<_>>();
+                format!("({})", elements.join(", "))
+            }
+            Expression::Projection(projection) => {
+                let tuple = self.traverse(&projection.tuple, current_scope);
+                if matches!(projection.tuple, Expression::Application(_))
+                    && !self.is_numeral(&projection.tuple)
+                {
+                    format!("({}).{}", tuple, projection.index)
+                } else {
+                    format!("{}.{}", tuple, projection.index)
+                }
+            }
+            Expression::Error(_) => "<error>".to_string(),
         }
     }
 }
@@ -122,7 +200,7 @@ mod tests {
         let input = PositionedBuffer::new("(λx.λy.λz. w x y z)");
         let (mut expression, _) = Expression::parse(input).unwrap();
         DeBruijnConverter::convert(&mut expression);
-        let pretty = ExpressionPrettyPrinter::format_named(&mut expression);
+        let pretty = ExpressionPrettyPrinter::format_named(&expression);
         assert_eq!(pretty, "λx. λy. λz. w x y z");
     }
 
@@ -131,7 +209,7 @@ mod tests {
         let input = PositionedBuffer::new("(λx.λy.λz. w x y z)");
         let (mut expression, _) = Expression::parse(input).unwrap();
         DeBruijnConverter::convert(&mut expression);
-        let pretty = ExpressionPrettyPrinter::format_nameless_locals(&mut expression);
+        let pretty = ExpressionPrettyPrinter::format_nameless_locals(&expression);
         assert_eq!(pretty, "λ λ λ w 3 2 1");
     }
 
@@ -140,7 +218,7 @@ mod tests {
         let input = PositionedBuffer::new("(λx.λy.λz. w x y z)");
         let (mut expression, _) = Expression::parse(input).unwrap();
         DeBruijnConverter::convert(&mut expression);
-        let pretty = ExpressionPrettyPrinter::format_indexed(&mut expression);
+        let pretty = ExpressionPrettyPrinter::format_indexed(&expression);
         assert_eq!(pretty, "λ λ λ 4 3 2 1");
     }
 
@@ -149,7 +227,7 @@ mod tests {
         let input = PositionedBuffer::new("λx y z.x z (y z)");
         let (mut expression, _) = Expression::parse(input).unwrap();
         DeBruijnConverter::convert(&mut expression);
-        let pretty = ExpressionPrettyPrinter::format_indexed(&mut expression);
+        let pretty = ExpressionPrettyPrinter::format_indexed(&expression);
         assert_eq!(pretty, "λ λ λ 3 1 (2 1)");
     }
 
@@ -158,7 +236,43 @@ mod tests {
         let input = PositionedBuffer::new("λx:T,y:U.x y z");
         let (mut expression, _) = Expression::parse(input).unwrap();
         DeBruijnConverter::convert(&mut expression);
-        let pretty = ExpressionPrettyPrinter::format_named(&mut expression);
+        let pretty = ExpressionPrettyPrinter::format_named(&expression);
         assert_eq!(pretty, "λx:T. λy:U. x y z");
     }
+
+    #[test]
+    fn test_let() {
+        let input = PositionedBuffer::new("let id = λx. x, y = id z in f (let w = y in w) y");
+        let (mut expression, _) = Expression::parse(input).unwrap();
+        DeBruijnConverter::convert(&mut expression);
+        let pretty = ExpressionPrettyPrinter::format_named(&expression);
+        assert_eq!(
+            pretty,
+            "let id = λx. x in let y = id z in f (let w = y in w) y"
+        );
+        let pretty = ExpressionPrettyPrinter::format_indexed(&expression);
+        assert_eq!(pretty, "let λ 1 in let 1 2 in 4 (let 1 in 1) 1");
+    }
+
+    #[test]
+    fn test_tuple() {
+        let input = PositionedBuffer::new("λx. (x, λy. y, (f x).1, (x, z).2.1)");
+        let (mut expression, _) = Expression::parse(input).unwrap();
+        DeBruijnConverter::convert(&mut expression);
+        let pretty = ExpressionPrettyPrinter::format_named(&expression);
+        assert_eq!(pretty, "λx. (x, λy. y, (f x).1, (x, z).2.1)");
+        let pretty = ExpressionPrettyPrinter::format_indexed(&expression);
+        assert_eq!(pretty, "λ (1, λ 1, (2 1).1, (1, 3).2.1)");
+    }
+
+    #[test]
+    fn test_readback() {
+        let input = PositionedBuffer::new("f (λs z. s (s z)) (λs z. z) λx. x");
+        let (mut expression, _) = Expression::parse(input).unwrap();
+        DeBruijnConverter::convert(&mut expression);
+        let pretty = ExpressionPrettyPrinter::format_readback(&expression, NumeralEncoding::Church);
+        assert_eq!(pretty, "f 2 0 (λx. x)");
+        let pretty = ExpressionPrettyPrinter::format_readback(&expression, NumeralEncoding::Scott);
+        assert_eq!(pretty, "f (λs. λz. s (s z)) (λs. λz. z) (λx. x)");
+    }
 }
@@ -1,4 +1,10 @@
-use crate::common::process_untyped;
+use f_prime::eval::full::FullBetaEvaluator;
+use f_prime::eval::BetaReduction;
+use f_prime::term::untyped::UntypedLambdaTerm;
+use f_prime::term::Term;
+use f_prime::traverse::pretty_print::ExpressionPrettyPrinter;
+
+use crate::common::{convert_de_bruijn, parse_expression, process_untyped};
 
 pub mod common;
 /// untyped lambda calculus combinators
@@ -77,3 +83,43 @@ fn turing_fixed_point() {
         "(λ λ 1 (2 2 1)) (λ λ 1 (2 2 1))",
     )
 }
+
+#[test]
+fn recursive_binding() {
+    assert_eq!(
+        process_untyped("letrec f = λx. f x in f"),
+        "letrec λ 2 1 in 1",
+    )
+}
+
+/// Factorial with Church numerals, where the recursive call sits under a thunk `λd`.
+const FACTORIAL: &str = "\
+    letrec fact = λn. iszero n (λd. 1) (λd. mult n (fact (pred n))) (λd. d) in fact";
+
+/// Normalizes fully, failing if it takes more than `steps` steps.
+fn normalize(source: &str, steps: usize) -> String {
+    let mut expression = parse_expression(source);
+    convert_de_bruijn(&mut expression);
+    let mut term = UntypedLambdaTerm::new(expression);
+    let taken = (0..=steps)
+        .take_while(|_| FullBetaEvaluator::reduce_once(&mut term))
+        .count();
+    assert!(taken < steps, "not normalized in {steps} steps");
+    ExpressionPrettyPrinter::format_indexed(term.as_expr())
+}
+
+#[test]
+fn recursive_binding_under_thunk() {
+    let source = format!(
+        "let true = λt f. t, false = λt f. f, iszero = λn. n (λx. false) true,
+             mult = λm n f. m (n f), pred = λn f x. n (λg h. h (g f)) (λu. x) (λu. u) in
+         {FACTORIAL} 3"
+    );
+    assert_eq!(normalize(&source, 10_000), "λ λ 2 (2 (2 (2 (2 (2 1)))))");
+}
+
+#[test]
+fn recursive_binding_stuck() {
+    let result = normalize(&format!("{FACTORIAL} 2"), 1_000);
+    assert!(result.contains("letrec"), "{result}");
+}